@@ -0,0 +1,123 @@
+use crate::{vector::Vec3, interval::Interval, ray::Ray};
+
+// Axis-aligned bounding box, one interval for each axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb{
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb{
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Aabb{
+        Aabb { x, y, z }
+    }
+
+    // Box with no volume, merging anything into it gives the other box.
+    pub fn empty() -> Aabb{
+        Aabb::new(Interval::empty(), Interval::empty(), Interval::empty())
+    }
+
+    // Box spanned by two opposite corners, given in any order.
+    pub fn from_points(a: Vec3, b: Vec3) -> Aabb{
+        Aabb::new(
+            Interval::new(a.x.min(b.x), a.x.max(b.x)),
+            Interval::new(a.y.min(b.y), a.y.max(b.y)),
+            Interval::new(a.z.min(b.z), a.z.max(b.z)),
+        )
+    }
+
+    // Smallest box enclosing both boxes.
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Aabb{
+        Aabb::new(
+            Interval::surrounding(&a.x, &b.x),
+            Interval::surrounding(&a.y, &b.y),
+            Interval::surrounding(&a.z, &b.z),
+        )
+    }
+
+    // Interval of axis 0 (x), 1 (y) or 2 (z).
+    pub fn axis(&self, axis: usize) -> Interval{
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.x.size() < 0.0 || self.y.size() < 0.0 || self.z.size() < 0.0
+    }
+
+    // Whether all bounds are finite, infinite objects like planes are not.
+    pub fn is_finite(&self) -> bool{
+        [self.x, self.y, self.z].iter().all(|interval| interval.min.is_finite() && interval.max.is_finite())
+    }
+
+    pub fn centroid(&self) -> Vec3{
+        Vec3::new(
+            (self.x.min + self.x.max) / 2.0,
+            (self.y.min + self.y.max) / 2.0,
+            (self.z.min + self.z.max) / 2.0,
+        )
+    }
+
+    pub fn surface_area(&self) -> f64{
+        if self.is_empty() {
+            return 0.0
+        }
+
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    // Axis along which the box is the longest.
+    pub fn longest_axis(&self) -> usize{
+        if self.x.size() > self.y.size() && self.x.size() > self.z.size() {
+            0
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
+    // Box with every axis thinner than `delta` widened to it, so flat objects still get hit.
+    pub fn pad(&self, delta: f64) -> Aabb{
+        let pad = |interval: Interval| if interval.size() < delta { interval.expand(delta) } else { interval };
+
+        Aabb::new(pad(self.x), pad(self.y), pad(self.z))
+    }
+
+    // Whether the ray passes through the box between t_min and t_max (slab test).
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool{
+        let direction = ray.direction();
+        let inverse_direction = Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+
+        self.hit_inverse(&ray.origin(), &inverse_direction, t_min, t_max)
+    }
+
+    // Slab test with reciprocal of the ray direction computed by caller, used when one ray tests many boxes.
+    pub fn hit_inverse(&self, origin: &Vec3, inverse_direction: &Vec3, mut t_min: f64, mut t_max: f64) -> bool{
+        let origin = [origin.x, origin.y, origin.z];
+        let inverse_direction = [inverse_direction.x, inverse_direction.y, inverse_direction.z];
+
+        for axis in 0..3 {
+            let interval = self.axis(axis);
+            let t0 = (interval.min - origin[axis]) * inverse_direction[axis];
+            let t1 = (interval.max - origin[axis]) * inverse_direction[axis];
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            // max/min written this way ignore NaN coming from 0 * infinity
+            if t0 > t_min { t_min = t0 }
+            if t1 < t_max { t_max = t1 }
+
+            if t_max < t_min {
+                return false
+            }
+        }
+
+        true
+    }
+}
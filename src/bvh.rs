@@ -0,0 +1,246 @@
+use crate::{vector::Vec3, aabb::Aabb, ray, hit_record::HitRecord, hittable::Hittable};
+
+// Number of buckets centroids are sorted into when looking for the cheapest split
+const SAH_BUCKETS: usize = 12;
+// Leaves can hold more objects than this only when their centroids can't be told apart
+const MAX_LEAF_OBJECTS: usize = 4;
+// Cost of testing a ray against child boxes, relative to testing it against one object
+const TRAVERSAL_COST: f64 = 0.125;
+
+#[derive(Debug, Clone, Copy)]
+enum BvhNodeKind{
+    // Objects [start, start + count) of the reordered object list
+    Leaf{ start: usize, count: usize },
+    // First child directly follows its parent, `second_child` is the index of the other one
+    Interior{ second_child: usize, axis: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BvhNode{
+    bounds: Aabb,
+    kind: BvhNodeKind,
+}
+
+// Object bounds and centroid used while building the tree.
+struct BuildObject{
+    index: usize,
+    bounds: Aabb,
+    centroid: Vec3,
+}
+
+// Bounding volume hierarchy built with the surface area heuristic, stored as a flat list of nodes in depth-first order.
+// Objects without finite bounds (planes) can't be sorted into the tree, they are tested against every ray.
+pub struct Bvh<T: Hittable>{
+    // Objects in the tree come first, in leaf order, unbounded objects follow them
+    objects: Vec<T>,
+    bounded_count: usize,
+    nodes: Vec<BvhNode>,
+}
+
+impl<T: Hittable> Bvh<T>{
+    pub fn new(objects: Vec<T>) -> Bvh<T>{
+        let mut build_objects = Vec::new();
+        let mut unbounded = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            let bounds = object.bounding_box();
+
+            if bounds.is_finite() {
+                build_objects.push(BuildObject { index, bounds, centroid: bounds.centroid() });
+            } else {
+                unbounded.push(index);
+            }
+        }
+
+        let mut nodes = Vec::new();
+        if !build_objects.is_empty() {
+            Bvh::<T>::build(&mut nodes, &mut build_objects, 0);
+        }
+
+        // Reorder objects so every leaf refers to a continuous range of them.
+        let mut slots: Vec<Option<T>> = objects.into_iter().map(Some).collect();
+        let objects = build_objects.iter().map(|object| object.index).chain(unbounded)
+            .map(|index| slots[index].take().unwrap())
+            .collect();
+
+        Bvh { objects, bounded_count: build_objects.len(), nodes }
+    }
+
+    pub fn len(&self) -> usize{
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.objects.is_empty()
+    }
+
+    pub fn objects(&self) -> &[T]{
+        &self.objects
+    }
+
+    // Builds the subtree of `objects`, which start at `offset` in the whole list, returns the index of its root node.
+    fn build(nodes: &mut Vec<BvhNode>, objects: &mut [BuildObject], offset: usize) -> usize{
+        let bounds = objects.iter().fold(Aabb::empty(), |bounds, object| Aabb::surrounding(&bounds, &object.bounds));
+        let centroid_bounds = objects.iter().fold(Aabb::empty(), |bounds, object| {
+            Aabb::surrounding(&bounds, &Aabb::from_points(object.centroid, object.centroid))
+        });
+
+        let node_index = nodes.len();
+        nodes.push(BvhNode { bounds, kind: BvhNodeKind::Leaf { start: offset, count: objects.len() } });
+
+        if objects.len() == 1 {
+            return node_index
+        }
+
+        let split = match Bvh::<T>::find_split(objects, &bounds, &centroid_bounds) {
+            Some(split) => split,
+            None => return node_index,
+        };
+
+        let (axis, mid) = split;
+        let (left, right) = objects.split_at_mut(mid);
+
+        Bvh::<T>::build(nodes, left, offset);
+        let second_child = Bvh::<T>::build(nodes, right, offset + mid);
+        nodes[node_index].kind = BvhNodeKind::Interior { second_child, axis };
+
+        node_index
+    }
+
+    // Picks the cheapest bucket boundary over all axes and partitions the objects around it.
+    // Returns the axis and the number of objects on the left side, or None when a leaf is cheaper.
+    fn find_split(objects: &mut [BuildObject], bounds: &Aabb, centroid_bounds: &Aabb) -> Option<(usize, usize)>{
+        let count = objects.len();
+        let mut best: Option<(f64, usize, usize)> = None;
+
+        for axis in 0..3 {
+            let extent = centroid_bounds.axis(axis);
+            if extent.size() <= 0.0 {
+                continue
+            }
+
+            let mut bucket_counts = [0usize; SAH_BUCKETS];
+            let mut bucket_bounds = [Aabb::empty(); SAH_BUCKETS];
+            for object in objects.iter() {
+                let bucket = bucket_index(&object.centroid, axis, centroid_bounds);
+                bucket_counts[bucket] += 1;
+                bucket_bounds[bucket] = Aabb::surrounding(&bucket_bounds[bucket], &object.bounds);
+            }
+
+            // Cost of splitting after each bucket, sweeping from both ends.
+            let mut right_area = [0.0; SAH_BUCKETS];
+            let mut right_count = [0usize; SAH_BUCKETS];
+            let mut right_bounds = Aabb::empty();
+            let mut running = 0;
+            for bucket in (1..SAH_BUCKETS).rev() {
+                right_bounds = Aabb::surrounding(&right_bounds, &bucket_bounds[bucket]);
+                running += bucket_counts[bucket];
+                right_area[bucket] = right_bounds.surface_area();
+                right_count[bucket] = running;
+            }
+
+            let mut left_bounds = Aabb::empty();
+            let mut left_count = 0;
+            for bucket in 0..SAH_BUCKETS - 1 {
+                left_bounds = Aabb::surrounding(&left_bounds, &bucket_bounds[bucket]);
+                left_count += bucket_counts[bucket];
+
+                if left_count == 0 || right_count[bucket + 1] == 0 {
+                    continue
+                }
+
+                let cost = left_bounds.surface_area() * left_count as f64 + right_area[bucket + 1] * right_count[bucket + 1] as f64;
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, bucket));
+                }
+            }
+        }
+
+        // No split found when all centroids are in the same spot
+        let (cost, axis, bucket) = best?;
+
+        let area = bounds.surface_area();
+        let split_cost = if area > 0.0 { TRAVERSAL_COST + cost / area } else { TRAVERSAL_COST };
+        if count <= MAX_LEAF_OBJECTS && split_cost >= count as f64 {
+            return None
+        }
+
+        // Partition objects into the buckets up to `bucket` and the rest.
+        let mut mid = 0;
+        for i in 0..count {
+            if bucket_index(&objects[i].centroid, axis, centroid_bounds) <= bucket {
+                objects.swap(i, mid);
+                mid += 1;
+            }
+        }
+
+        Some((axis, mid))
+    }
+}
+
+// Bucket of the centroid along `axis`, buckets evenly divide the centroid bounds.
+fn bucket_index(centroid: &Vec3, axis: usize, centroid_bounds: &Aabb) -> usize{
+    let extent = centroid_bounds.axis(axis);
+    let value = [centroid.x, centroid.y, centroid.z][axis];
+
+    (((value - extent.min) / extent.size() * SAH_BUCKETS as f64) as usize).min(SAH_BUCKETS - 1)
+}
+
+impl<T: Hittable> Hittable for Bvh<T>{
+    fn hit(&self, ray: &ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut closest = t_max;
+        let mut hit_record = None;
+
+        for object in &self.objects[self.bounded_count..] {
+            if let Some(hit) = object.hit(ray, t_min, closest) {
+                closest = hit.t;
+                hit_record = Some(hit);
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return hit_record
+        }
+
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let inverse_direction = Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let direction_negative = [direction.x < 0.0, direction.y < 0.0, direction.z < 0.0];
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+
+            if !node.bounds.hit_inverse(&origin, &inverse_direction, t_min, closest) {
+                continue
+            }
+
+            match node.kind {
+                BvhNodeKind::Leaf { start, count } => {
+                    for object in &self.objects[start..start + count] {
+                        if let Some(hit) = object.hit(ray, t_min, closest) {
+                            closest = hit.t;
+                            hit_record = Some(hit);
+                        }
+                    }
+                }
+                BvhNodeKind::Interior { second_child, axis } => {
+                    // Visit the child closer to the ray origin first, so farther objects get culled by `closest`.
+                    let (near, far) = if direction_negative[axis] { (second_child, node_index + 1) } else { (node_index + 1, second_child) };
+
+                    stack.push(far);
+                    stack.push(near);
+                }
+            }
+        }
+
+        hit_record
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bounds = self.nodes.first().map_or(Aabb::empty(), |root| root.bounds);
+
+        self.objects[self.bounded_count..].iter().fold(bounds, |bounds, object| Aabb::surrounding(&bounds, &object.bounding_box()))
+    }
+}
@@ -1,4 +1,8 @@
-use crate::vector;
+use std::fmt;
+use crate::{vector, sampler::SamplerType, filter::{Filter, FilterType}};
+
+// Largest image side in pixels, anything bigger is most likely a typo
+pub const MAX_IMAGE_SIZE: i32 = 65536;
 
 #[derive(Debug)]
 pub struct Camera{
@@ -9,49 +13,320 @@ pub struct Camera{
     pub pixel_vec_u: vector::Vec3,
     pub pixel_vec_v: vector::Vec3,
     pub max_depth: u32,
+    pub samples_per_pixel: u32,
+    // Seed of all random numbers used for rendering, same seed gives identical image
+    pub seed: u64,
+    pub sampler: SamplerType,
+    // Reconstruction filter weighting samples into pixels
+    pub filter: Filter,
+    // Camera frame basis vectors
+    pub u: vector::Vec3,
+    pub v: vector::Vec3,
+    pub w: vector::Vec3,
+    // Variation angle of rays through each pixel, 0 disables defocus blur
+    pub defocus_angle: f64,
+    pub defocus_disk_u: vector::Vec3,
+    pub defocus_disk_v: vector::Vec3,
+}
+
+// Settings used to place the camera in the scene, finished off with `build`.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraBuilder{
+    image_width: i32,
+    image_height: Option<i32>,
+    aspect_ratio: f64,
+    max_depth: u32,
+    samples_per_pixel: u32,
+    seed: u64,
+    sampler: SamplerType,
+    filter_type: FilterType,
+    filter_radius: Option<f64>,
+    vfov: f64,
+    look_from: vector::Vec3,
+    look_at: vector::Vec3,
+    vup: vector::Vec3,
+    defocus_angle: f64,
+    focus_dist: Option<f64>,
+}
+
+// Camera settings which don't describe a view, `build` would give NaN rays and a black image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraError{
+    // look_from and look_at are the same point
+    ZeroViewDirection,
+    // vup is zero or points along the view direction, so there is no way to tell left from right
+    UpParallelToView,
+    // Vertical field of view has to be between 0 and 180 degrees
+    Vfov(f64),
+    // Focus distance has to be a finite number above zero
+    FocusDist(f64),
+    // Aspect ratio has to be a finite number above zero
+    AspectRatio(f64),
+    ImageWidth(i32),
+    // Given height, or the one following from width and aspect ratio
+    ImageHeight(i32),
+    SamplesPerPixel,
+    // Filter radius has to be at least half a pixel
+    FilterRadius(f64),
 }
 
+impl fmt::Display for CameraError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraError::ZeroViewDirection => write!(f, "camera look_from and look_at must be different points"),
+            CameraError::UpParallelToView => write!(f, "camera vup must not be zero or parallel to the view direction, set another vup"),
+            CameraError::Vfov(vfov) => write!(f, "camera vfov must be between 0 and 180 degrees, got {}", vfov),
+            CameraError::FocusDist(focus_dist) => write!(f, "camera focus_dist must be a finite number above zero, got {}", focus_dist),
+            CameraError::AspectRatio(aspect_ratio) => write!(f, "camera aspect_ratio must be a finite number above zero, got {}", aspect_ratio),
+            CameraError::ImageWidth(width) => write!(f, "image width must be between 1 and {} pixels, got {}", MAX_IMAGE_SIZE, width),
+            CameraError::ImageHeight(height) => write!(f, "image height must be between 1 and {} pixels, got {}", MAX_IMAGE_SIZE, height),
+            CameraError::SamplesPerPixel => write!(f, "samples_per_pixel must be at least 1"),
+            CameraError::FilterRadius(radius) => write!(f, "filter radius must be a finite number of at least 0.5 pixels, got {}", radius),
+        }
+    }
+}
+
+impl std::error::Error for CameraError {}
+
 impl Camera{
-    pub fn new(image_width: i32, max_depth: u32) -> Camera{
+    pub fn new(image_width: i32, max_depth: u32) -> Result<Camera, CameraError>{
+        Camera::builder(image_width, max_depth).build()
+    }
 
-        //Image
-        const ASPECT_RATIO: f64 = 16.0/9.0;
-
-        // Calculate the image height, and ensure that it's at least 1.
-        let mut image_height =  image_width / ASPECT_RATIO as i32;
-        
-        image_height =  match image_height {
-            0..=1 => 1,
-            _ => image_height,
+    pub fn builder(image_width: i32, max_depth: u32) -> CameraBuilder{
+        CameraBuilder::new(image_width, max_depth)
+    }
+
+    // Returns a point in the camera defocus disk for 2D sample `u`.
+    pub fn defocus_disk_sample(&self, u: (f64, f64)) -> vector::Vec3{
+        let p = vector::Vec3::sample_unit_disk(u);
+
+        self.center + (self.defocus_disk_u * p.x()) + (self.defocus_disk_v * p.y())
+    }
+}
+
+impl CameraBuilder{
+    // Defaults match the original fixed camera: 16:9 image, origin, looking down -Z, 90 degree field of view.
+    pub fn new(image_width: i32, max_depth: u32) -> CameraBuilder{
+        CameraBuilder {
+            image_width,
+            image_height: None,
+            aspect_ratio: 16.0 / 9.0,
+            max_depth,
+            samples_per_pixel: 32,
+            seed: 0,
+            sampler: SamplerType::Sobol,
+            filter_type: FilterType::Box,
+            filter_radius: None,
+            vfov: 90.0,
+            look_from: vector::Vec3::new(0.0, 0.0, 0.0),
+            look_at: vector::Vec3::new(0.0, 0.0, -1.0),
+            vup: vector::Vec3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.0,
+            focus_dist: None,
+        }
+    }
+
+    pub fn image_width(mut self, image_width: i32) -> CameraBuilder{
+        self.image_width = image_width;
+        self
+    }
+
+    // Ratio of image width over height, the height is derived from the width.
+    pub fn aspect_ratio(mut self, aspect_ratio: f64) -> CameraBuilder{
+        self.aspect_ratio = aspect_ratio;
+        self.image_height = None;
+        self
+    }
+
+    // Explicit image height, overrides the aspect ratio.
+    pub fn image_height(mut self, image_height: i32) -> CameraBuilder{
+        self.image_height = Some(image_height);
+        self
+    }
+
+    // Maximum number of ray bounces
+    pub fn max_depth(mut self, max_depth: u32) -> CameraBuilder{
+        self.max_depth = max_depth;
+        self
+    }
+
+    // Count of random samples for each pixel
+    pub fn samples_per_pixel(mut self, samples_per_pixel: u32) -> CameraBuilder{
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> CameraBuilder{
+        self.seed = seed;
+        self
+    }
+
+    // Sample pattern used for pixels, lens and scattering
+    pub fn sampler(mut self, sampler: SamplerType) -> CameraBuilder{
+        self.sampler = sampler;
+        self
+    }
+
+    // Pixel reconstruction filter, box filter of the pixel size by default
+    pub fn filter(mut self, filter_type: FilterType) -> CameraBuilder{
+        self.filter_type = filter_type;
+        self
+    }
+
+    // Filter radius in pixels, defaults to the usual radius of the chosen filter
+    pub fn filter_radius(mut self, filter_radius: f64) -> CameraBuilder{
+        self.filter_radius = Some(filter_radius);
+        self
+    }
+
+    // Vertical field of view in degrees
+    pub fn vfov(mut self, vfov: f64) -> CameraBuilder{
+        self.vfov = vfov;
+        self
+    }
+
+    pub fn look_from(mut self, look_from: vector::Vec3) -> CameraBuilder{
+        self.look_from = look_from;
+        self
+    }
+
+    pub fn look_at(mut self, look_at: vector::Vec3) -> CameraBuilder{
+        self.look_at = look_at;
+        self
+    }
+
+    // Camera-relative "up" direction
+    pub fn vup(mut self, vup: vector::Vec3) -> CameraBuilder{
+        self.vup = vup;
+        self
+    }
+
+    // Variation angle of rays through each pixel in degrees
+    pub fn defocus_angle(mut self, defocus_angle: f64) -> CameraBuilder{
+        self.defocus_angle = defocus_angle;
+        self
+    }
+
+    // Distance from look_from to the plane of perfect focus, defaults to the look_at point
+    pub fn focus_dist(mut self, focus_dist: f64) -> CameraBuilder{
+        self.focus_dist = Some(focus_dist);
+        self
+    }
+
+    // Checks that the settings describe a view of a sensible size, `build` does the same.
+    pub fn validate(&self) -> Result<(), CameraError>{
+        self.image_size()?;
+        self.pixel_filter()?;
+        if self.samples_per_pixel == 0 {
+            return Err(CameraError::SamplesPerPixel)
+        }
+
+        let view = self.look_from - self.look_at;
+        if view.near_zero() {
+            return Err(CameraError::ZeroViewDirection)
+        }
+        // Zero vup fails this too
+        if self.vup.cross(&view.unit_vector()).length() <= 1e-9 * self.vup.length() {
+            return Err(CameraError::UpParallelToView)
+        }
+        if !(self.vfov > 0.0 && self.vfov < 180.0) {
+            return Err(CameraError::Vfov(self.vfov))
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            if !focus_dist.is_finite() || focus_dist <= 0.0 {
+                return Err(CameraError::FocusDist(focus_dist))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn pixel_filter(&self) -> Result<Filter, CameraError>{
+        let radius = self.filter_radius.unwrap_or(self.filter_type.default_radius());
+
+        Filter::new(self.filter_type, radius).ok_or(CameraError::FilterRadius(radius))
+    }
+
+    // Width and height of the image, the height follows from the aspect ratio unless it's given.
+    fn image_size(&self) -> Result<(i32, i32), CameraError>{
+        if !(1..=MAX_IMAGE_SIZE).contains(&self.image_width) {
+            return Err(CameraError::ImageWidth(self.image_width))
+        }
+
+        let image_height = match self.image_height {
+            Some(image_height) => image_height,
+            None => {
+                if !self.aspect_ratio.is_finite() || self.aspect_ratio <= 0.0 {
+                    return Err(CameraError::AspectRatio(self.aspect_ratio))
+                }
+
+                // Saturating cast, too tall images are caught below
+                (self.image_width as f64 / self.aspect_ratio) as i32
+            }
         };
-        
+        if !(1..=MAX_IMAGE_SIZE).contains(&image_height) {
+            return Err(CameraError::ImageHeight(image_height))
+        }
+
+        Ok((self.image_width, image_height))
+    }
+
+    pub fn build(self) -> Result<Camera, CameraError>{
+        self.validate()?;
+
+        //Image
+        let (image_width, image_height) = self.image_size()?;
+
         // Camera
-        let focal_length = 1.0;
-        let viewport_height = 2.0;
-        let viewport_width = viewport_height * (image_height / image_width) as f64;
-        let center = vector::Vec3::new(0.0, 0.0, 0.0);
+        let center = self.look_from;
+        let focus_dist = self.focus_dist.unwrap_or((self.look_from - self.look_at).length());
+        let theta = self.vfov.to_radians();
+        let h = (theta / 2.0).tan();
+        let viewport_height = 2.0 * h * focus_dist;
+        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
+
+        // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
+        let w = (self.look_from - self.look_at).unit_vector();
+        let u = self.vup.cross(&w).unit_vector();
+        let v = w.cross(&u);
 
         // Vectors for horizontal and vertical lines
-        let vecotr_u = vector::Vec3::new(viewport_width, 0.0, 0.0);
-        let vector_v = vector::Vec3::new(0.0, -viewport_height, 0.0);
+        let vecotr_u = u * viewport_width;
+        let vector_v = -v * viewport_height;
 
         // Delta vectors for pixels
         let pixel_vec_u = vecotr_u / image_width as f64;
         let pixel_vec_v = vector_v / image_height as f64;
 
         // Calculate location of the upper left pixel
-        let viewpoert_upper_left = center - vector::Vec3::new(0.0, 0.0, focal_length) - (vecotr_u/2.0) - (vector_v/2.0);
+        let viewpoert_upper_left = center - (w * focus_dist) - (vecotr_u/2.0) - (vector_v/2.0);
         let mut pixel00_loc = (pixel_vec_u + pixel_vec_v) * 0.5;
         pixel00_loc = pixel00_loc + viewpoert_upper_left;
 
-        Camera { 
+        // Calculate the camera defocus disk basis vectors.
+        let defocus_radius = focus_dist * (self.defocus_angle / 2.0).to_radians().tan();
+        let defocus_disk_u = u * defocus_radius;
+        let defocus_disk_v = v * defocus_radius;
+
+        Ok(Camera {
             image_width,
             image_height,
             pixel00_loc,
             pixel_vec_u,
             pixel_vec_v,
             center,
-            max_depth
-        }
+            max_depth: self.max_depth,
+            samples_per_pixel: self.samples_per_pixel,
+            seed: self.seed,
+            sampler: self.sampler,
+            filter: self.pixel_filter()?,
+            u,
+            v,
+            w,
+            defocus_angle: self.defocus_angle,
+            defocus_disk_u,
+            defocus_disk_v,
+        })
     }
-}
\ No newline at end of file
+}
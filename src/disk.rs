@@ -0,0 +1,66 @@
+use crate::{ray, hit_record, vector, interval::Interval, aabb::Aabb, material::Material, hittable::Hittable, plane::PARALLEL_EPSILON};
+
+// Flat round disk around `center`, facing the direction of `normal`.
+pub struct Disk{
+    center: vector::Vec3,
+    normal: vector::Vec3,
+    radius: f64,
+    tangent: vector::Vec3,
+    bitangent: vector::Vec3,
+    material: Material,
+}
+
+impl Disk{
+    pub fn new(center: vector::Vec3, normal: vector::Vec3, radius: f64, material: Material) -> Disk {
+        let normal = normal.unit_vector();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+
+        Disk {
+            center,
+            normal,
+            radius,
+            tangent,
+            bitangent,
+            material,
+        }
+    }
+}
+
+impl Hittable for Disk{
+    fn hit(&self, ray: &ray::Ray, t_min: f64, t_max: f64) -> Option<hit_record::HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None
+        }
+
+        let t = self.normal.dot(&(self.center - ray.origin())) / denominator;
+        if t <= t_min || t >= t_max {
+            return None
+        }
+
+        let offset = ray.at(t) - self.center;
+        if offset.length_squared() > self.radius * self.radius {
+            return None
+        }
+
+        // Square around the disk is mapped to [0, 1] x [0, 1].
+        let uv = (
+            0.5 + offset.dot(&self.tangent) / (2.0 * self.radius),
+            0.5 + offset.dot(&self.bitangent) / (2.0 * self.radius),
+        );
+
+        Some(hit_record::HitRecord::from_outward_normal(ray, t, self.normal, uv, self.material))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // Disk reaches radius * sin(angle between the normal and the axis) along each axis.
+        let extent = |n: f64| self.radius * (1.0 - n * n).max(0.0).sqrt();
+        let around = |center: f64, n: f64| Interval::new(center - extent(n), center + extent(n));
+
+        Aabb::new(
+            around(self.center.x(), self.normal.x()),
+            around(self.center.y(), self.normal.y()),
+            around(self.center.z(), self.normal.z()),
+        ).pad(0.0001)
+    }
+}
@@ -0,0 +1,102 @@
+// Piecewise-constant distribution over [0, 1), picks values proportionally to a step function.
+#[derive(Debug, Clone)]
+pub struct Distribution1D{
+    function: Vec<f64>,
+    // Running integral of the function normalized to end at 1, one more entry than the function
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D{
+    // Negative values count as zero, an all zero function is sampled uniformly.
+    pub fn new(function: &[f64]) -> Distribution1D{
+        assert!(!function.is_empty(), "distribution needs at least one value");
+
+        let function: Vec<f64> = function.iter().map(|value| value.max(0.0)).collect();
+        let n = function.len() as f64;
+
+        let mut cdf = Vec::with_capacity(function.len() + 1);
+        cdf.push(0.0);
+        for value in &function {
+            cdf.push(cdf.last().unwrap() + value / n);
+        }
+
+        let integral = *cdf.last().unwrap();
+        if integral > 0.0 {
+            cdf.iter_mut().for_each(|value| *value /= integral);
+        } else {
+            cdf.iter_mut().enumerate().for_each(|(i, value)| *value = i as f64 / n);
+        }
+        *cdf.last_mut().unwrap() = 1.0;
+
+        Distribution1D { function, cdf, integral }
+    }
+
+    pub fn len(&self) -> usize{
+        self.function.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.function.is_empty()
+    }
+
+    // Average of the function over [0, 1)
+    pub fn integral(&self) -> f64{
+        self.integral
+    }
+
+    // Value picked by `u` from [0, 1), its density and the index of its step.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize){
+        // Last cdf entry not above u, steps of zero width are never picked.
+        let index = (self.cdf.partition_point(|value| *value <= u) - 1).min(self.len() - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 { (u - self.cdf[index]) / width } else { 0.0 };
+
+        ((index as f64 + offset) / self.len() as f64, self.step_pdf(index), index)
+    }
+
+    // Density of picking `x` from [0, 1).
+    pub fn pdf(&self, x: f64) -> f64{
+        let index = ((x * self.len() as f64) as usize).min(self.len() - 1);
+
+        self.step_pdf(index)
+    }
+
+    fn step_pdf(&self, index: usize) -> f64{
+        if self.integral > 0.0 { self.function[index] / self.integral } else { 1.0 }
+    }
+}
+
+// Piecewise-constant distribution over [0, 1)^2 given by rows of values, a row is picked first and then a column in it.
+#[derive(Debug, Clone)]
+pub struct Distribution2D{
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D{
+    // `function` holds `width` values per row, row 0 is at v = 0.
+    pub fn new(function: &[f64], width: usize) -> Distribution2D{
+        assert!(width > 0 && !function.is_empty() && function.len().is_multiple_of(width), "distribution function must be made of full rows");
+
+        let rows: Vec<Distribution1D> = function.chunks_exact(width).map(Distribution1D::new).collect();
+        let marginal = Distribution1D::new(&rows.iter().map(Distribution1D::integral).collect::<Vec<f64>>());
+
+        Distribution2D { rows, marginal }
+    }
+
+    // Point (u, v) picked by a 2D sample, with its density.
+    pub fn sample(&self, sample: (f64, f64)) -> ((f64, f64), f64){
+        let (v, marginal_pdf, row) = self.marginal.sample(sample.1);
+        let (u, conditional_pdf, _) = self.rows[row].sample(sample.0);
+
+        ((u, v), marginal_pdf * conditional_pdf)
+    }
+
+    pub fn pdf(&self, u: f64, v: f64) -> f64{
+        let row = ((v * self.rows.len() as f64) as usize).min(self.rows.len() - 1);
+
+        self.marginal.pdf(v) * self.rows[row].pdf(u)
+    }
+}
@@ -0,0 +1,201 @@
+use std::{fmt, path::{Path, PathBuf}};
+use palette::Srgb;
+use std::f64::consts::PI;
+use crate::{vector::Vec3, texture::ImageTexture, distribution::Distribution2D};
+
+// Light coming from far away, seen by every ray which leaves the scene.
+#[derive(Debug, Clone)]
+pub enum Environment{
+    Constant(Srgb),
+    // Blend from the color straight down to the color straight up
+    Gradient{ bottom: Srgb, top: Srgb },
+    Map(EnvironmentMap),
+}
+
+impl Environment{
+    // Blue-white sky, the default environment
+    pub fn sky() -> Environment{
+        Environment::Gradient { bottom: Srgb::new(1.0, 1.0, 1.0), top: Srgb::new(0.5, 0.7, 1.0) }
+    }
+
+    // Light arriving from `direction`, which doesn't have to be a unit vector.
+    pub fn color(&self, direction: Vec3) -> Srgb{
+        match self {
+            Environment::Constant(color) => *color,
+            Environment::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.unit_vector().y() as f32 + 1.0);
+
+                *bottom * (1.0 - t) + *top * t
+            }
+            Environment::Map(map) => map.color(direction),
+        }
+    }
+
+    // Direction picked towards bright parts of the environment, its light and density over solid angle.
+    // None when the environment isn't worth sampling directly, which rays find well enough by bouncing.
+    pub fn sample(&self, sample: (f64, f64)) -> Option<(Vec3, Srgb, f64)>{
+        match self {
+            Environment::Map(map) => map.sample(sample),
+            _ => None,
+        }
+    }
+
+    // Density of `sample` picking `direction`, None for environments without sampling.
+    pub fn pdf(&self, direction: Vec3) -> Option<f64>{
+        match self {
+            Environment::Map(map) => Some(map.pdf(direction)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Environment{
+    fn default() -> Self {
+        Environment::sky()
+    }
+}
+
+// Equirectangular (latitude-longitude) image around the scene, its center is seen looking down -Z.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap{
+    image: ImageTexture,
+    // Texels picked by their brightness, so directions to a small sun are found often
+    distribution: Distribution2D,
+    // Turn around the vertical axis in degrees
+    rotation: f64,
+    intensity: f32,
+}
+
+impl EnvironmentMap{
+    pub fn new(image: ImageTexture) -> EnvironmentMap{
+        let (width, height) = (image.width(), image.height());
+
+        // Rows near the poles cover less of the sphere, the sine of their angle scales them down.
+        let mut luminance = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+
+            for x in 0..width {
+                let texel = image.get(x, y);
+                luminance.push((0.2126 * texel.red + 0.7152 * texel.green + 0.0722 * texel.blue) as f64 * sin_theta);
+            }
+        }
+
+        let distribution = Distribution2D::new(&luminance, width as usize);
+
+        EnvironmentMap { image, distribution, rotation: 0.0, intensity: 1.0 }
+    }
+
+    // Loads a Radiance .hdr or OpenEXR .exr image, any other format image can read works too but is taken as linear.
+    pub fn load(path: &Path) -> Result<EnvironmentMap, EnvironmentError>{
+        let error = |error| match error {
+            image::ImageError::IoError(error) => EnvironmentError::Io(path.to_path_buf(), error),
+            error => EnvironmentError::Format(path.to_path_buf(), error.to_string()),
+        };
+
+        // image opens .hdr files as 8-bit, which would clip the sun, so they are decoded here.
+        let is_hdr = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+        if is_hdr {
+            let file = std::fs::File::open(path).map_err(|io| EnvironmentError::Io(path.to_path_buf(), io))?;
+            let decoder = image::codecs::hdr::HdrDecoder::new(std::io::BufReader::new(file)).map_err(error)?;
+            let metadata = decoder.metadata();
+            let pixels = decoder.read_image_hdr().map_err(error)?.iter().map(|pixel| Srgb::new(pixel[0], pixel[1], pixel[2])).collect();
+
+            return Ok(EnvironmentMap::new(ImageTexture::new(metadata.width, metadata.height, pixels)))
+        }
+
+        let image = image::open(path).map_err(error)?.into_rgb32f();
+        let pixels = image.pixels().map(|pixel| Srgb::new(pixel[0], pixel[1], pixel[2])).collect();
+
+        Ok(EnvironmentMap::new(ImageTexture::new(image.width(), image.height(), pixels)))
+    }
+
+    pub fn with_rotation(mut self, degrees: f64) -> EnvironmentMap{
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> EnvironmentMap{
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn image(&self) -> &ImageTexture{
+        &self.image
+    }
+
+    pub fn rotation(&self) -> f64{
+        self.rotation
+    }
+
+    pub fn intensity(&self) -> f32{
+        self.intensity
+    }
+
+    pub fn color(&self, direction: Vec3) -> Srgb{
+        let (u, v) = self.uv(direction);
+
+        // Rows don't wrap around the poles, so v is kept within the first and last row.
+        let half_row = 0.5 / self.image.height() as f64;
+
+        self.image.sample(u, v.clamp(half_row, 1.0 - half_row)) * self.intensity
+    }
+
+    // Image coordinates of a direction, u grows with the angle around +Y and v from the top (+Y) to the bottom.
+    pub fn uv(&self, direction: Vec3) -> (f64, f64){
+        let d = direction.unit_vector();
+        let phi = d.x().atan2(-d.z()) + self.rotation.to_radians();
+        let theta = d.y().clamp(-1.0, 1.0).acos();
+
+        ((0.5 + phi / (2.0 * PI)).rem_euclid(1.0), theta / PI)
+    }
+
+    // Unit vector of image coordinates, the inverse of `uv`.
+    pub fn direction(&self, u: f64, v: f64) -> Vec3{
+        let phi = (u - 0.5) * 2.0 * PI - self.rotation.to_radians();
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+
+        Vec3::new(sin_theta * phi.sin(), cos_theta, -sin_theta * phi.cos())
+    }
+
+    pub fn sample(&self, sample: (f64, f64)) -> Option<(Vec3, Srgb, f64)>{
+        let ((u, v), uv_pdf) = self.distribution.sample(sample);
+        let sin_theta = (v * PI).sin();
+        if uv_pdf == 0.0 || sin_theta <= 0.0 {
+            return None
+        }
+
+        let direction = self.direction(u, v);
+
+        // Image area maps to solid angle by 2 pi^2 sin(theta).
+        Some((direction, self.color(direction), uv_pdf / (2.0 * PI * PI * sin_theta)))
+    }
+
+    pub fn pdf(&self, direction: Vec3) -> f64{
+        let (u, v) = self.uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0
+        }
+
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+#[derive(Debug)]
+pub enum EnvironmentError{
+    Io(PathBuf, std::io::Error),
+    // File could be read, but it isn't an image we can decode
+    Format(PathBuf, String),
+}
+
+impl fmt::Display for EnvironmentError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvironmentError::Io(path, error) => write!(f, "failed to read environment map {}: {}", path.display(), error),
+            EnvironmentError::Format(path, message) => write!(f, "failed to load environment map {}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for EnvironmentError {}
@@ -0,0 +1,133 @@
+use palette::Srgb;
+use crate::{filter::Filter, framebuffer::Framebuffer};
+
+// Sum of weighted samples and of their weights for one pixel, together with the plain sum of samples
+// taken inside of it. Negative filter lobes can leave no weight, the plain average is used then.
+#[derive(Debug, Clone, Copy)]
+struct FilmPixel{
+    color: Srgb,
+    weight: f32,
+    box_color: Srgb,
+    box_count: u32,
+}
+
+impl Default for FilmPixel{
+    fn default() -> Self {
+        FilmPixel { color: Srgb::new(0.0, 0.0, 0.0), weight: 0.0, box_color: Srgb::new(0.0, 0.0, 0.0), box_count: 0 }
+    }
+}
+
+// Image being rendered, every sample is splatted with the filter to all pixels it reaches.
+#[derive(Debug, Clone)]
+pub struct Film{
+    width: u32,
+    height: u32,
+    filter: Filter,
+    pixels: Vec<FilmPixel>,
+}
+
+// Rows of the film rendered by one thread, together with the border reached by the filter.
+#[derive(Debug, Clone)]
+pub struct FilmTile{
+    width: u32,
+    // First film row covered by the tile and number of covered rows
+    y0: u32,
+    rows: u32,
+    filter: Filter,
+    pixels: Vec<FilmPixel>,
+}
+
+impl Film{
+    pub fn new(width: u32, height: u32, filter: Filter) -> Film{
+        Film {
+            width,
+            height,
+            filter,
+            pixels: vec![FilmPixel::default(); width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32{
+        self.width
+    }
+
+    pub fn height(&self) -> u32{
+        self.height
+    }
+
+    // Tile for rendering samples of rows [y_start, y_end), it also covers rows those samples splat into.
+    pub fn tile(&self, y_start: u32, y_end: u32) -> FilmTile{
+        let padding = self.filter.radius.ceil() as u32;
+        let y0 = y_start.saturating_sub(padding);
+        let rows = y_end.saturating_add(padding).min(self.height) - y0;
+
+        FilmTile {
+            width: self.width,
+            y0,
+            rows,
+            filter: self.filter,
+            pixels: vec![FilmPixel::default(); self.width as usize * rows as usize],
+        }
+    }
+
+    // Adds tile sums to the film, merging tiles in the same order always gives identical image.
+    pub fn merge_tile(&mut self, tile: &FilmTile){
+        let start = tile.y0 as usize * self.width as usize;
+
+        for (pixel, tile_pixel) in self.pixels[start..start + tile.pixels.len()].iter_mut().zip(&tile.pixels) {
+            pixel.color += tile_pixel.color;
+            pixel.weight += tile_pixel.weight;
+            pixel.box_color += tile_pixel.box_color;
+            pixel.box_count += tile_pixel.box_count;
+        }
+    }
+
+    // Filtered colors of all pixels. Pixels without positive weight get the average of their own samples,
+    // pixels without any samples are left black.
+    pub fn to_framebuffer(&self) -> Framebuffer{
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+
+        for (pixel, film_pixel) in framebuffer.pixels_mut().iter_mut().zip(&self.pixels) {
+            if film_pixel.weight > 0.0 {
+                *pixel = film_pixel.color * (1.0 / film_pixel.weight);
+            } else if film_pixel.box_count > 0 {
+                *pixel = film_pixel.box_color * (1.0 / film_pixel.box_count as f32);
+            }
+        }
+
+        framebuffer
+    }
+}
+
+impl FilmTile{
+    // Splats a sample taken at continuous film position (x, y), pixel (i, j) spans [i, i + 1) x [j, j + 1).
+    pub fn add_sample(&mut self, x: f64, y: f64, color: Srgb){
+        let radius = self.filter.radius;
+
+        let (i, j) = (x.floor() as i64, y.floor() as i64);
+        if (0..self.width as i64).contains(&i) && (self.y0 as i64..(self.y0 + self.rows) as i64).contains(&j) {
+            let pixel = &mut self.pixels[((j - self.y0 as i64) * self.width as i64 + i) as usize];
+            pixel.box_color += color;
+            pixel.box_count += 1;
+        }
+
+        // Pixels whose center lies within (-radius, radius] of the sample.
+        let x_min = ((x - 0.5 - radius).floor() + 1.0).max(0.0) as i64;
+        let x_max = (x - 0.5 + radius).floor().min(self.width as f64 - 1.0) as i64;
+        let y_min = ((y - 0.5 - radius).floor() + 1.0).max(self.y0 as f64) as i64;
+        let y_max = (y - 0.5 + radius).floor().min((self.y0 + self.rows) as f64 - 1.0) as i64;
+
+        for j in y_min..=y_max {
+            for i in x_min..=x_max {
+                let weight = self.filter.evaluate(i as f64 + 0.5 - x, j as f64 + 0.5 - y) as f32;
+                if weight == 0.0 {
+                    continue
+                }
+
+                let pixel = &mut self.pixels[((j - self.y0 as i64) * self.width as i64 + i) as usize];
+                pixel.color += color * weight;
+                pixel.weight += weight;
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::f64::consts::PI;
+
+// Shape of the pixel reconstruction filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterType{
+    // Every sample inside of the radius counts the same, radius 0.5 gives a plain average of the pixel
+    Box,
+    // Weight falls off linearly towards the radius
+    Tent,
+    // Gaussian bell with standard deviation of a third of the radius, shifted to reach zero at the radius
+    Gaussian,
+    // Mitchell-Netravali cubic with B = C = 1/3, sharp with small negative lobes
+    Mitchell,
+    // Sinc windowed by a wider sinc, sharpest of all, but can ring around hard edges
+    Lanczos,
+}
+
+impl FilterType{
+    // Radius in pixels the filter is usually used with.
+    pub fn default_radius(&self) -> f64{
+        match self {
+            FilterType::Box => 0.5,
+            FilterType::Tent => 1.0,
+            FilterType::Gaussian => 1.5,
+            FilterType::Mitchell => 2.0,
+            FilterType::Lanczos => 3.0,
+        }
+    }
+}
+
+// Separable filter used to weight samples by their distance from pixel centers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Filter{
+    pub filter_type: FilterType,
+    // Half width of the filter support in pixels, same in both directions
+    pub radius: f64,
+}
+
+impl Filter{
+    // None for radii below half a pixel, which would leave pixels without samples of their own, and for infinite ones.
+    pub fn new(filter_type: FilterType, radius: f64) -> Option<Filter>{
+        (radius.is_finite() && radius >= 0.5).then_some(Filter { filter_type, radius })
+    }
+
+    // Weight of a sample at offset (x, y) in pixels from the pixel center.
+    pub fn evaluate(&self, x: f64, y: f64) -> f64{
+        if x.abs() > self.radius || y.abs() > self.radius {
+            return 0.0
+        }
+
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64{
+        let x = x.abs();
+        let radius = self.radius;
+
+        match self.filter_type {
+            FilterType::Box => 1.0,
+            FilterType::Tent => (radius - x).max(0.0),
+            FilterType::Gaussian => {
+                let sigma = radius / 3.0;
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+
+                (gaussian(x) - gaussian(radius)).max(0.0)
+            }
+            FilterType::Mitchell => mitchell(2.0 * x / radius, 1.0 / 3.0, 1.0 / 3.0),
+            FilterType::Lanczos => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+impl Default for Filter{
+    fn default() -> Self {
+        Filter { filter_type: FilterType::Box, radius: FilterType::Box.default_radius() }
+    }
+}
+
+// Mitchell-Netravali cubic over [-2, 2].
+fn mitchell(x: f64, b: f64, c: f64) -> f64{
+    let x = x.abs();
+
+    if x < 1.0 {
+        ((12.0 - 9.0 * b - 6.0 * c) * x.powi(3) + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2) + (6.0 - 2.0 * b)) / 6.0
+    } else if x < 2.0 {
+        ((-b - 6.0 * c) * x.powi(3) + (6.0 * b + 30.0 * c) * x.powi(2) + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)) / 6.0
+    } else {
+        0.0
+    }
+}
+
+fn sinc(x: f64) -> f64{
+    if x.abs() < 1e-5 {
+        return 1.0
+    }
+
+    (PI * x).sin() / (PI * x)
+}
@@ -0,0 +1,49 @@
+use palette::Srgb;
+
+// In-memory image, pixels are stored as linear (not gamma encoded) colors in row major order.
+#[derive(Debug, Clone)]
+pub struct Framebuffer{
+    width: u32,
+    height: u32,
+    pixels: Vec<Srgb>,
+}
+
+impl Framebuffer{
+    pub fn new(width: u32, height: u32) -> Framebuffer{
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Srgb::new(0.0, 0.0, 0.0); width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32{
+        self.width
+    }
+
+    pub fn height(&self) -> u32{
+        self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Srgb{
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: Srgb){
+        let index = self.index(x, y);
+        self.pixels[index] = color;
+    }
+
+    // Counted in usize, width * height of a big image doesn't fit u32.
+    fn index(&self, x: u32, y: u32) -> usize{
+        y as usize * self.width as usize + x as usize
+    }
+
+    pub fn pixels(&self) -> &[Srgb]{
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Srgb]{
+        &mut self.pixels
+    }
+}
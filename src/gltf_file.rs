@@ -0,0 +1,212 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+use palette::Srgb;
+use crate::{vector::{Vec3, Mat4}, camera::CameraBuilder, triangle::Triangle, mesh::{TriangleMesh, MeshError},
+    texture::{ImageTexture, PbrTextures}, material::{Material, Pbr}};
+
+// Meshes and camera of a glTF scene, with all node transforms already applied.
+pub struct GltfScene{
+    pub meshes: Vec<TriangleMesh>,
+    // First perspective camera of the scene, if there is any
+    pub camera: Option<GltfCamera>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GltfCamera{
+    pub look_from: Vec3,
+    pub look_at: Vec3,
+    pub vup: Vec3,
+    // Vertical field of view in degrees
+    pub vfov: f64,
+    pub aspect_ratio: Option<f64>,
+}
+
+impl GltfCamera{
+    // Places the camera of the builder where the glTF camera is.
+    pub fn apply(&self, mut builder: CameraBuilder) -> CameraBuilder{
+        builder = builder.look_from(self.look_from).look_at(self.look_at).vup(self.vup).vfov(self.vfov);
+
+        match self.aspect_ratio {
+            Some(aspect_ratio) => builder.aspect_ratio(aspect_ratio),
+            None => builder,
+        }
+    }
+}
+
+// Loads a .gltf or .glb file with its buffers and images, every mesh primitive becomes one triangle mesh.
+pub fn load(path: &Path) -> Result<GltfScene, MeshError>{
+    let (document, buffers, images) = gltf::import(path).map_err(|error| match error {
+        gltf::Error::Io(error) => MeshError::Io(path.to_path_buf(), error),
+        error => MeshError::Format(path.to_path_buf(), error.to_string()),
+    })?;
+
+    let mut loader = Loader { path, buffers: &buffers, images: &images, textures: HashMap::new(), meshes: Vec::new(), camera: None };
+
+    if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+        for node in scene.nodes() {
+            loader.load_node(&node, &Mat4::IDENTITY)?;
+        }
+    }
+
+    Ok(GltfScene { meshes: loader.meshes, camera: loader.camera })
+}
+
+struct Loader<'a>{
+    path: &'a Path,
+    buffers: &'a [gltf::buffer::Data],
+    images: &'a [gltf::image::Data],
+    // Decoded images by their index and whether they hold sRGB colors
+    textures: HashMap<(usize, bool), Arc<ImageTexture>>,
+    meshes: Vec<TriangleMesh>,
+    camera: Option<GltfCamera>,
+}
+
+impl Loader<'_>{
+    fn load_node(&mut self, node: &gltf::Node, parent: &Mat4) -> Result<(), MeshError>{
+        let local = Mat4::from_columns(node.transform().matrix().map(|column| column.map(|value| value as f64)));
+        let transform = parent.compose(&local);
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.load_primitive(&mesh, &primitive, &transform)?;
+            }
+        }
+
+        if let (None, Some(camera)) = (&self.camera, node.camera()) {
+            if let gltf::camera::Projection::Perspective(perspective) = camera.projection() {
+                // Cameras look down their local -Z axis with +Y up.
+                let look_from = transform.transform_point(Vec3::new(0.0, 0.0, 0.0));
+                let forward = transform.transform_vector(Vec3::new(0.0, 0.0, -1.0));
+
+                self.camera = Some(GltfCamera {
+                    look_from,
+                    look_at: look_from + forward.unit_vector(),
+                    vup: transform.transform_vector(Vec3::new(0.0, 1.0, 0.0)),
+                    vfov: (perspective.yfov() as f64).to_degrees(),
+                    aspect_ratio: perspective.aspect_ratio().map(|aspect_ratio| aspect_ratio as f64),
+                });
+            }
+        }
+
+        for child in node.children() {
+            self.load_node(&child, &transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn load_primitive(&mut self, mesh: &gltf::Mesh, primitive: &gltf::Primitive, transform: &Mat4) -> Result<(), MeshError>{
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            return Ok(())
+        }
+
+        let name = mesh.name().map_or_else(|| format!("mesh {}", mesh.index()), str::to_string);
+        let reader = primitive.reader(|buffer| self.buffers.get(buffer.index()).map(|data| &data.0[..]));
+
+        let positions: Vec<Vec3> = match reader.read_positions() {
+            Some(positions) => positions.map(|p| transform.transform_point(to_vec3(p))).collect(),
+            None => return Ok(()),
+        };
+
+        // Normals are transformed by the inverse transpose to stay perpendicular to the surface.
+        let normal_transform = transform.inverse().unwrap_or(Mat4::IDENTITY).transpose();
+        let normals: Option<Vec<Vec3>> = reader.read_normals()
+            .map(|normals| normals.map(|n| normal_transform.transform_vector(to_vec3(n)).unit_vector()).collect());
+        let uvs: Option<Vec<(f64, f64)>> = reader.read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(|uv| (uv[0] as f64, uv[1] as f64)).collect());
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+
+        // Mirroring transforms turn the triangles inside out, swapping two vertices keeps them facing outwards.
+        let mirrored = transform.determinant3() < 0.0;
+        let (material, textures) = self.load_material(&primitive.material());
+
+        let mut triangles = Vec::with_capacity(indices.len() / 3);
+        for face in indices.chunks_exact(3) {
+            let [a, b, c] = if mirrored { [face[0], face[2], face[1]] } else { [face[0], face[1], face[2]] }.map(|i| i as usize);
+            if a.max(b).max(c) >= positions.len() {
+                return Err(MeshError::Format(self.path.to_path_buf(), format!("primitive of `{}` refers to missing vertex", name)));
+            }
+
+            let mut triangle = Triangle::new(positions[a], positions[b], positions[c], material);
+            if let Some(normals) = normals.as_ref().filter(|normals| normals.len() == positions.len()) {
+                triangle = triangle.with_normals([normals[a], normals[b], normals[c]]);
+            }
+            if let Some(uvs) = uvs.as_ref().filter(|uvs| uvs.len() == positions.len()) {
+                triangle = triangle.with_uvs([uvs[a], uvs[b], uvs[c]]);
+            }
+
+            triangles.push(triangle);
+        }
+
+        if !triangles.is_empty() {
+            self.meshes.push(TriangleMesh::new(&name, triangles).with_textures(textures));
+        }
+
+        Ok(())
+    }
+
+    fn load_material(&mut self, material: &gltf::Material) -> (Material, PbrTextures){
+        let pbr = material.pbr_metallic_roughness();
+        let [red, green, blue, _] = pbr.base_color_factor();
+
+        let textures = PbrTextures {
+            base_color: pbr.base_color_texture().and_then(|info| self.texture(info.texture().source().index(), true)),
+            metallic_roughness: pbr.metallic_roughness_texture().and_then(|info| self.texture(info.texture().source().index(), false)),
+            emissive: material.emissive_texture().and_then(|info| self.texture(info.texture().source().index(), true)),
+        };
+
+        let [emit_red, emit_green, emit_blue] = material.emissive_factor();
+        let pbr = Pbr::new(Srgb::new(red, green, blue), pbr.metallic_factor() as f64, pbr.roughness_factor() as f64)
+            .with_emission(Srgb::new(emit_red, emit_green, emit_blue));
+
+        (Material::Pbr(pbr), textures)
+    }
+
+    // Decoded image, every image is decoded only once.
+    fn texture(&mut self, index: usize, srgb: bool) -> Option<Arc<ImageTexture>>{
+        if let Some(texture) = self.textures.get(&(index, srgb)) {
+            return Some(texture.clone())
+        }
+
+        let texture = Arc::new(decode_image(self.images.get(index)?, srgb)?);
+        self.textures.insert((index, srgb), texture.clone());
+
+        Some(texture)
+    }
+}
+
+// Turns glTF image data of any supported channel layout into a texture, None for unknown formats.
+fn decode_image(image: &gltf::image::Data, srgb: bool) -> Option<ImageTexture>{
+    use gltf::image::Format;
+
+    let (channels, bytes) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        _ => return None,
+    };
+
+    // 16-bit channels are reduced to their high byte
+    let channel = |texel: &[u8], c: usize| texel[c * bytes + bytes - 1];
+    let rgb: Vec<[u8; 3]> = image.pixels.chunks_exact(channels * bytes).map(|texel| match channels {
+        1 | 2 => [channel(texel, 0); 3],
+        _ => [channel(texel, 0), channel(texel, 1), channel(texel, 2)],
+    }).collect();
+
+    if rgb.len() != (image.width * image.height) as usize {
+        return None
+    }
+
+    Some(ImageTexture::from_rgb8(image.width, image.height, &rgb, srgb))
+}
+
+fn to_vec3(v: [f32; 3]) -> Vec3{
+    Vec3::new(v[0] as f64, v[1] as f64, v[2] as f64)
+}
@@ -1,10 +1,13 @@
-use crate::{vector, material, sphere::{self, Hittable}, interval, ray};
+use crate::{vector, material, hittable::Hittable, interval, ray};
 pub struct HitRecord{
     pub point: vector::Vec3,
     pub normal: vector::Vec3,
     pub material: material::Material,
     pub t: f64,
     pub front_face: bool,
+    // Surface coordinates of the hit point, used for texturing
+    pub u: f64,
+    pub v: f64,
 }
 
 impl HitRecord{
@@ -15,21 +18,28 @@ impl HitRecord{
             normal,
             front_face,
             material,
+            u: 0.0,
+            v: 0.0,
         }
     }
 
-    pub fn hit_world(world: &Vec<sphere::Sphere>, ray: &ray::Ray, intensity: interval::Interval) -> Option<HitRecord> {
-        let mut closest = intensity.max;
-    
-        let mut hit_record = None;
-    
-        for sphere in world {
-            if let Some(hit) = sphere.hit(ray, intensity.min, closest) {
-                closest = hit.t;
-                hit_record = Some(hit);
-            }
+    // Hit at distance `t` along the ray, normal is flipped against the ray so it always points to the side the ray came from.
+    pub fn from_outward_normal(ray: &ray::Ray, t: f64, outward_normal: vector::Vec3, uv: (f64, f64), material: material::Material) -> HitRecord {
+        let front_face = ray.direction().dot(&outward_normal) < 0.0;
+
+        HitRecord {
+            t,
+            point: ray.at(t),
+            normal: if front_face { outward_normal } else { -outward_normal },
+            front_face,
+            material,
+            u: uv.0,
+            v: uv.1,
         }
-    
-        hit_record
+    }
+
+    // Closest hit of the ray inside of the interval, the world is usually a BVH so only nearby objects get tested.
+    pub fn hit_world(world: &dyn Hittable, ray: &ray::Ray, intensity: interval::Interval) -> Option<HitRecord> {
+        world.hit(ray, intensity.min, intensity.max)
     }
 }
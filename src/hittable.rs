@@ -0,0 +1,93 @@
+use std::sync::Arc;
+use crate::{ray, hit_record::HitRecord, aabb::Aabb};
+
+// Objects are shared between render threads, so they have to be Send + Sync.
+pub trait Hittable: Send + Sync{
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+
+    // Box enclosing the whole object, used to skip objects a ray can't hit.
+    fn bounding_box(&self) -> Aabb;
+}
+
+impl Hittable for Box<dyn Hittable>{
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.as_ref().hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.as_ref().bounding_box()
+    }
+}
+
+impl Hittable for Arc<dyn Hittable>{
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.as_ref().hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.as_ref().bounding_box()
+    }
+}
+
+// Any mix of objects, tested one after another.
+pub struct HittableList{
+    objects: Vec<Box<dyn Hittable>>,
+    bounds: Aabb,
+}
+
+impl HittableList{
+    pub fn new() -> HittableList{
+        HittableList { objects: Vec::new(), bounds: Aabb::empty() }
+    }
+
+    pub fn add<H: Hittable + 'static>(&mut self, object: H){
+        self.push(Box::new(object));
+    }
+
+    pub fn push(&mut self, object: Box<dyn Hittable>){
+        self.bounds = Aabb::surrounding(&self.bounds, &object.bounding_box());
+        self.objects.push(object);
+    }
+
+    pub fn len(&self) -> usize{
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.objects.is_empty()
+    }
+
+    pub fn objects(&self) -> &[Box<dyn Hittable>]{
+        &self.objects
+    }
+
+    pub fn into_objects(self) -> Vec<Box<dyn Hittable>>{
+        self.objects
+    }
+}
+
+impl Default for HittableList{
+    fn default() -> Self {
+        HittableList::new()
+    }
+}
+
+impl Hittable for HittableList{
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut closest = t_max;
+        let mut hit_record = None;
+
+        for object in &self.objects {
+            if let Some(hit) = object.hit(r, t_min, closest) {
+                closest = hit.t;
+                hit_record = Some(hit);
+            }
+        }
+
+        hit_record
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
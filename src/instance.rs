@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use crate::{ray, hit_record::HitRecord, aabb::Aabb, interval::Interval, vector::{Vec3, Transform}, hittable::Hittable};
+
+// Shared object placed in the scene with a transform, so one mesh can be used many times without copying it.
+pub struct Instance{
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    bounds: Aabb,
+}
+
+impl Instance{
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Instance{
+        let bounds = transform_box(&object.bounding_box(), &transform);
+
+        Instance { object, transform, bounds }
+    }
+
+    pub fn object(&self) -> &Arc<dyn Hittable>{
+        &self.object
+    }
+
+    pub fn transform(&self) -> &Transform{
+        &self.transform
+    }
+}
+
+impl Hittable for Instance{
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Direction is not normalized, so distances along the ray are the same in both spaces.
+        let inverse = self.transform.inverse_matrix();
+        let object_ray = ray::Ray::new(inverse.transform_point(r.origin()), inverse.transform_vector(r.direction()));
+
+        let mut hit = self.object.hit(&object_ray, t_min, t_max)?;
+
+        // Inverse transpose keeps the sign of normal and direction dot product, so front face stays right.
+        hit.point = self.transform.point(hit.point);
+        hit.normal = self.transform.normal(hit.normal);
+
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+// Box around the transformed corners of `bounds`, infinite boxes stay infinite.
+fn transform_box(bounds: &Aabb, transform: &Transform) -> Aabb{
+    if bounds.is_empty() {
+        return *bounds
+    }
+    if !bounds.is_finite() {
+        let infinite = Interval::new(f64::NEG_INFINITY, f64::INFINITY);
+
+        return Aabb::new(infinite, infinite, infinite)
+    }
+
+    let mut result = Aabb::empty();
+    for corner in 0..8 {
+        let x = if corner & 1 == 0 { bounds.x.min } else { bounds.x.max };
+        let y = if corner & 2 == 0 { bounds.y.min } else { bounds.y.max };
+        let z = if corner & 4 == 0 { bounds.z.min } else { bounds.z.max };
+
+        let point = transform.point(Vec3::new(x, y, z));
+        result = Aabb::surrounding(&result, &Aabb::from_points(point, point));
+    }
+
+    result
+}
@@ -9,6 +9,27 @@ impl Interval{
         Interval { min, max }
     }
 
+    // Interval containing nothing, grows to the first value it's merged with.
+    pub fn empty() -> Interval{
+        Interval { min: f64::INFINITY, max: f64::NEG_INFINITY }
+    }
+
+    // Smallest interval enclosing both intervals.
+    pub fn surrounding(a: &Interval, b: &Interval) -> Interval{
+        Interval { min: a.min.min(b.min), max: a.max.max(b.max) }
+    }
+
+    pub fn size(&self) -> f64{
+        self.max - self.min
+    }
+
+    // Interval widened by `delta` in total, half on each side.
+    pub fn expand(&self, delta: f64) -> Interval{
+        let padding = delta / 2.0;
+
+        Interval { min: self.min - padding, max: self.max + padding }
+    }
+
     // fn contains(&self, x: f64) -> bool {
     //     self.min <= x && x <= self.max
     // }
@@ -17,19 +38,10 @@ impl Interval{
     //     self.min < x && x < self.max
     // }
 
-    fn clamp(&self, x: f32) -> f64 {
+    pub fn clamp(&self, x: f32) -> f64 {
         if self.min > x as f64 {return self.min}
         if self.max < x as f64 {return self.max}
     
         x as f64
     }
-
-    pub fn sample(&self,color: f32, pixels_per_sample: i32) -> f64 {
-
-        let scale = 1.0 / pixels_per_sample as f32;
-
-        let new_color = color * scale;
-
-        self.clamp(new_color)
-    }
 }
@@ -1,9 +1,35 @@
+#[cfg(test)]
 mod test;
 pub mod vector;
 pub mod render;
 pub mod interval;
+pub mod aabb;
+pub mod bvh;
 pub mod ray;
+pub mod hittable;
+pub mod instance;
 pub mod sphere;
+pub mod plane;
+pub mod disk;
+pub mod quad;
+pub mod triangle;
+pub mod mesh;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+pub mod texture;
+pub mod gltf_file;
 pub mod material;
 pub mod hit_record;
 pub mod camera;
+pub mod framebuffer;
+pub mod output;
+pub mod scene;
+pub mod environment;
+pub mod light;
+pub mod scene_file;
+pub mod tonemap;
+pub mod sampler;
+pub mod distribution;
+pub mod filter;
+pub mod film;
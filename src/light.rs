@@ -0,0 +1,145 @@
+use std::f64::consts::PI;
+use crate::{vector::Vec3, plane::PARALLEL_EPSILON};
+
+// Shape of a glowing object which shadow rays are aimed at. Lights are registered in the scene next to the objects
+// giving off the light, the light itself is read from the material of whatever the shadow ray hits.
+#[derive(Debug, Clone, Copy)]
+pub enum Light{
+    // Parallelogram with corner at `origin`, spanned by edges `u` and `v`
+    Quad{ origin: Vec3, u: Vec3, v: Vec3 },
+    // Triangle with corner at `origin` and the other corners at the ends of edges `u` and `v`
+    Triangle{ origin: Vec3, u: Vec3, v: Vec3 },
+    Sphere{ center: Vec3, radius: f64 },
+    // Flat round disk, `normal` is a unit vector
+    Disk{ center: Vec3, normal: Vec3, radius: f64 },
+}
+
+impl Light{
+    pub fn quad(origin: Vec3, u: Vec3, v: Vec3) -> Light{
+        Light::Quad { origin, u, v }
+    }
+
+    pub fn triangle(a: Vec3, b: Vec3, c: Vec3) -> Light{
+        Light::Triangle { origin: a, u: b - a, v: c - a }
+    }
+
+    pub fn sphere(center: Vec3, radius: f64) -> Light{
+        Light::Sphere { center, radius }
+    }
+
+    pub fn disk(center: Vec3, normal: Vec3, radius: f64) -> Light{
+        Light::Disk { center, normal: normal.unit_vector(), radius }
+    }
+
+    // Direction from `origin` towards a point picked on the light, with its density over solid angle.
+    // None when the light can't be seen from `origin`, like from inside a sphere or from the plane of a quad.
+    pub fn sample(&self, origin: Vec3, sample: (f64, f64)) -> Option<(Vec3, f64)>{
+        let direction = match *self {
+            // Area is picked uniformly, the pdf converts it to solid angle
+            Light::Quad { origin: corner, u, v } => corner + u * sample.0 + v * sample.1 - origin,
+            // Samples past the diagonal are folded back into the triangle
+            Light::Triangle { origin: corner, u, v } => {
+                let (a, b) = if sample.0 + sample.1 > 1.0 { (1.0 - sample.0, 1.0 - sample.1) } else { sample };
+
+                corner + u * a + v * b - origin
+            }
+            Light::Disk { center, normal, radius } => {
+                let (tangent, bitangent) = normal.orthonormal_basis();
+                let point = Vec3::sample_unit_disk(sample) * radius;
+
+                center + tangent * point.x + bitangent * point.y - origin
+            }
+            // Cone of directions which meet the sphere is picked uniformly
+            Light::Sphere { center, radius } => {
+                let to_center = center - origin;
+                let distance_squared = to_center.length_squared();
+                if distance_squared <= radius * radius {
+                    return None
+                }
+
+                let one_minus_cos_max = cone_one_minus_cos(radius * radius / distance_squared);
+                let cos_theta = 1.0 - sample.0 * one_minus_cos_max;
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * sample.1;
+
+                let axis = to_center.unit_vector();
+                let (tangent, bitangent) = axis.orthonormal_basis();
+
+                tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta
+            }
+        };
+
+        let pdf = self.pdf(origin, direction);
+
+        (pdf > 0.0).then_some((direction, pdf))
+    }
+
+    // Density of `sample` picking `direction` from `origin`, zero for directions which miss the light.
+    pub fn pdf(&self, origin: Vec3, direction: Vec3) -> f64{
+        match *self {
+            Light::Quad { origin: corner, u, v } => match plane_hit(corner, u, v, origin, direction) {
+                Some((alpha, beta, per_area)) if (0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta) => per_area / u.cross(&v).length(),
+                _ => 0.0,
+            }
+            Light::Triangle { origin: corner, u, v } => match plane_hit(corner, u, v, origin, direction) {
+                Some((alpha, beta, per_area)) if alpha >= 0.0 && beta >= 0.0 && alpha + beta <= 1.0 => 2.0 * per_area / u.cross(&v).length(),
+                _ => 0.0,
+            }
+            Light::Disk { center, normal, radius } => {
+                // Unit tangents make the plane coordinates distances from the center
+                let (tangent, bitangent) = normal.orthonormal_basis();
+                match plane_hit(center, tangent, bitangent, origin, direction) {
+                    Some((alpha, beta, per_area)) if alpha * alpha + beta * beta <= radius * radius => per_area / (PI * radius * radius),
+                    _ => 0.0,
+                }
+            }
+            Light::Sphere { center, radius } => {
+                let to_center = center - origin;
+                let distance_squared = to_center.length_squared();
+                if distance_squared <= radius * radius {
+                    return 0.0
+                }
+
+                let sin_squared_max = radius * radius / distance_squared;
+                let cos_theta = to_center.dot(&direction) / (distance_squared.sqrt() * direction.length());
+                if cos_theta < (1.0 - sin_squared_max).sqrt() {
+                    return 0.0
+                }
+
+                1.0 / (2.0 * PI * cone_one_minus_cos(sin_squared_max))
+            }
+        }
+    }
+}
+
+// Where `direction` from `origin` meets the plane spanned by edges `u` and `v` at `corner`, as multiples of the edges,
+// with the solid angle density of a unit of area there. None when the plane is behind, edge on or degenerate.
+fn plane_hit(corner: Vec3, u: Vec3, v: Vec3, origin: Vec3, direction: Vec3) -> Option<(f64, f64, f64)>{
+    let n = u.cross(&v);
+    let area = n.length();
+    let denominator = n.dot(&direction);
+    if area == 0.0 || (denominator / area).abs() < PARALLEL_EPSILON {
+        return None
+    }
+
+    let t = n.dot(&(corner - origin)) / denominator;
+    if t <= 0.0 {
+        return None
+    }
+
+    let planar = origin + direction * t - corner;
+    let w = n / n.dot(&n);
+    let alpha = w.dot(&planar.cross(&v));
+    let beta = w.dot(&u.cross(&planar));
+
+    // Area seen at distance d under angle theta covers area * cos(theta) / d^2 of solid angle.
+    let distance_squared = (direction * t).length_squared();
+    let cosine = denominator.abs() / (area * direction.length());
+
+    Some((alpha, beta, distance_squared / cosine))
+}
+
+// 1 - cos of a cone's half angle from its squared sine, accurate for small distant spheres.
+fn cone_one_minus_cos(sin_squared: f64) -> f64{
+    sin_squared / (1.0 + (1.0 - sin_squared).sqrt())
+}
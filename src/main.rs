@@ -1,10 +1,216 @@
+use std::{path::PathBuf, process};
+use clap::{Parser, ValueEnum};
 use rt::*;
 
-mod render;
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Tonemap {
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SamplerArg {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FilterArg {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Png,
+    Jpeg,
+    Ppm,
+    Exr,
+}
+
+/// Simple ray tracer
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// TOML scene file, the built-in demo scene is rendered when omitted
+    #[arg(short, long)]
+    scene: Option<PathBuf>,
+
+    /// Output image path, image is written to stdout when omitted
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Output format, guessed from the output extension by default
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// JPEG quality, up to 100
+    #[arg(long, default_value_t = 90, value_parser = clap::value_parser!(u8).range(1..=100))]
+    quality: u8,
+
+    /// Tone mapping curve applied before sRGB encoding of 8-bit formats
+    #[arg(short, long, value_enum, default_value = "clamp")]
+    tonemap: Tonemap,
+
+    /// Exposure adjustment in stops
+    #[arg(short, long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f32,
+
+    /// Image width in pixels
+    #[arg(short = 'W', long)]
+    width: Option<i32>,
+
+    /// Image height in pixels, derived from the aspect ratio when omitted
+    #[arg(short = 'H', long)]
+    height: Option<i32>,
+
+    /// Samples per pixel
+    #[arg(long)]
+    samples: Option<u32>,
+
+    /// Maximum number of ray bounces
+    #[arg(long)]
+    max_depth: Option<u32>,
+
+    /// Seed of the random sampling, same seed renders identical image
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Sample pattern for pixels, lens and scattering
+    #[arg(long, value_enum)]
+    sampler: Option<SamplerArg>,
 
+    /// Pixel reconstruction filter
+    #[arg(long, value_enum)]
+    filter: Option<FilterArg>,
+
+    /// Radius of the reconstruction filter in pixels
+    #[arg(long)]
+    filter_radius: Option<f64>,
+
+    /// Equirectangular .hdr or .exr image lighting the scene instead of its own environment
+    #[arg(long)]
+    environment: Option<PathBuf>,
+
+    /// Turn of the environment image around the vertical axis in degrees
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    environment_rotation: f64,
+
+    /// Brightness multiplier of the environment image
+    #[arg(long, default_value_t = 1.0)]
+    environment_intensity: f32,
+
+    /// Number of render threads, all CPU cores are used by default
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Don't report progress on stderr
+    #[arg(short, long)]
+    quiet: bool,
+}
 
 fn main() {
-    let camera = rt::camera::Camera::new(600, 50);
+    let cli = Cli::parse();
+
+    if let Some(threads) = cli.threads {
+        if let Err(error) = rayon::ThreadPoolBuilder::new().num_threads(threads).build_global() {
+            fail(&format!("Failed to start render threads: {}", error));
+        }
+    }
+
+    let (mut scene, mut builder) = match &cli.scene {
+        Some(path) => match scene_file::load(path) {
+            Ok(description) => (description.scene, description.camera),
+            Err(error) => fail(&error.to_string()),
+        },
+        None => (scene::Scene::demo(), camera::Camera::builder(600, 50)),
+    };
+
+    if let Some(path) = &cli.environment {
+        match environment::EnvironmentMap::load(path) {
+            Ok(map) => {
+                let map = map.with_rotation(cli.environment_rotation).with_intensity(cli.environment_intensity);
+                scene = scene.with_environment(environment::Environment::Map(map));
+            }
+            Err(error) => fail(&error.to_string()),
+        }
+    }
+
+    if let Some(width) = cli.width { builder = builder.image_width(width) }
+    if let Some(height) = cli.height { builder = builder.image_height(height) }
+    if let Some(samples) = cli.samples { builder = builder.samples_per_pixel(samples) }
+    if let Some(max_depth) = cli.max_depth { builder = builder.max_depth(max_depth) }
+    if let Some(seed) = cli.seed { builder = builder.seed(seed) }
+    if let Some(sampler) = cli.sampler {
+        builder = builder.sampler(match sampler {
+            SamplerArg::Independent => sampler::SamplerType::Independent,
+            SamplerArg::Stratified => sampler::SamplerType::Stratified,
+            SamplerArg::Halton => sampler::SamplerType::Halton,
+            SamplerArg::Sobol => sampler::SamplerType::Sobol,
+        });
+    }
+
+    if let Some(filter) = cli.filter {
+        builder = builder.filter(match filter {
+            FilterArg::Box => filter::FilterType::Box,
+            FilterArg::Tent => filter::FilterType::Tent,
+            FilterArg::Gaussian => filter::FilterType::Gaussian,
+            FilterArg::Mitchell => filter::FilterType::Mitchell,
+            FilterArg::Lanczos => filter::FilterType::Lanczos,
+        });
+    }
+    if let Some(filter_radius) = cli.filter_radius { builder = builder.filter_radius(filter_radius) }
+
+    let camera = match builder.build() {
+        Ok(camera) => camera,
+        Err(error) => fail(&error.to_string()),
+    };
+
+    let format = match cli.format {
+        Some(Format::Png) => output::OutputFormat::Png,
+        Some(Format::Jpeg) => output::OutputFormat::Jpeg(cli.quality),
+        Some(Format::Ppm) => output::OutputFormat::Ppm,
+        Some(Format::Exr) => output::OutputFormat::Exr,
+        None => match &cli.output {
+            Some(path) => match output::OutputFormat::from_path(path) {
+                Some(output::OutputFormat::Jpeg(_)) => output::OutputFormat::Jpeg(cli.quality),
+                Some(format) => format,
+                None => fail(&format!("Unknown image format of {}, use --format", path.display())),
+            },
+            None => output::OutputFormat::Png,
+        },
+    };
+
+    let tone_mapper = match cli.tonemap {
+        Tonemap::Clamp => tonemap::ToneMapper::Clamp,
+        Tonemap::Reinhard => tonemap::ToneMapper::Reinhard,
+        Tonemap::Aces => tonemap::ToneMapper::AcesFilmic,
+    };
+    let transform = tonemap::OutputTransform::new(cli.exposure, tone_mapper);
+
+    let framebuffer = if cli.quiet {
+        render::Render::render_to_buffer(&scene, &camera)
+    } else {
+        render::Render::render_to_buffer_with_progress(&scene, &camera)
+    };
+
+    let result = match &cli.output {
+        Some(path) => output::save(&framebuffer, format, &transform, path),
+        None => output::write(&framebuffer, format, &transform, &mut std::io::stdout().lock()),
+    };
+
+    if let Err(error) = result {
+        fail(&format!("Failed to write image: {}", error));
+    }
+}
 
-    render::Render::render(camera);
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
@@ -1,10 +1,32 @@
 
-use rand::{Rng, rngs::ThreadRng};
-use crate::{vector, ray, hit_record};
+use crate::{vector, ray, hit_record, sampler::Sampler};
 use palette::Srgb;
 
+// Light leaving a surface is what it emits plus what it scatters, `scatter` returns None for absorbed rays.
 pub trait Scatterable {
-    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Option<(ray::Ray, Srgb)>;
+    // Scattered ray with its attenuation and the density its direction was picked with,
+    // the density is None for mirror-like lobes which send the ray into a single direction.
+    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, sampler: &mut dyn Sampler) -> Option<(ray::Ray, Srgb, Option<f64>)>;
+
+    // Light given off at the hit point back along the ray, most materials don't glow.
+    fn emitted(&self, _ray: &ray::Ray, _hit_record: &hit_record::HitRecord) -> Srgb {
+        Srgb::new(0.0, 0.0, 0.0)
+    }
+
+    // Density over solid angle of `scatter` picking `direction`, zero where it never goes.
+    fn scattering_pdf(&self, _ray: &ray::Ray, _hit_record: &hit_record::HitRecord, _direction: vector::Vec3) -> f64 {
+        0.0
+    }
+
+    // Share of light arriving from `direction` that is sent back along the ray, scattering attenuation is this divided by the pdf.
+    fn evaluate(&self, _ray: &ray::Ray, _hit_record: &hit_record::HitRecord, _direction: vector::Vec3) -> Srgb {
+        Srgb::new(0.0, 0.0, 0.0)
+    }
+
+    // Mirrors and glass scatter into single directions, which light sampling can't hit.
+    fn is_specular(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -12,18 +34,79 @@ pub enum Material {
     Lambertian(Lambertian),
     Metallic(Metal),
     Glass(Glass),
+    Pbr(Pbr),
+    DiffuseLight(DiffuseLight),
 }
 
 impl Scatterable for Material {
-    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Option<(ray::Ray, Srgb)> {
+    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, sampler: &mut dyn Sampler) -> Option<(ray::Ray, Srgb, Option<f64>)> {
         match self {
-            Material::Lambertian(l) => l.scatter(ray, hit_record),
-            Material::Metallic(m) => m.scatter(ray, hit_record),
-            Material::Glass(g) => g.scatter(ray, hit_record),
+            Material::Lambertian(l) => l.scatter(ray, hit_record, sampler),
+            Material::Metallic(m) => m.scatter(ray, hit_record, sampler),
+            Material::Glass(g) => g.scatter(ray, hit_record, sampler),
+            Material::Pbr(p) => p.scatter(ray, hit_record, sampler),
+            Material::DiffuseLight(l) => l.scatter(ray, hit_record, sampler),
+        }
+    }
+
+    fn emitted(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Srgb {
+        match self {
+            Material::Pbr(p) => p.emitted(ray, hit_record),
+            Material::DiffuseLight(l) => l.emitted(ray, hit_record),
+            _ => Srgb::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn scattering_pdf(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> f64 {
+        match self {
+            Material::Lambertian(l) => l.scattering_pdf(ray, hit_record, direction),
+            Material::Metallic(m) => m.scattering_pdf(ray, hit_record, direction),
+            Material::Pbr(p) => p.scattering_pdf(ray, hit_record, direction),
+            Material::Glass(_) | Material::DiffuseLight(_) => 0.0,
+        }
+    }
+
+    fn evaluate(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> Srgb {
+        match self {
+            Material::Lambertian(l) => l.evaluate(ray, hit_record, direction),
+            Material::Metallic(m) => m.evaluate(ray, hit_record, direction),
+            Material::Pbr(p) => p.evaluate(ray, hit_record, direction),
+            Material::Glass(_) | Material::DiffuseLight(_) => Srgb::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        match self {
+            Material::Lambertian(l) => l.is_specular(),
+            Material::Metallic(m) => m.is_specular(),
+            Material::Pbr(p) => p.is_specular(),
+            Material::Glass(_) | Material::DiffuseLight(_) => true,
         }
     }
 }
 
+impl Material {
+    // Whether any light is given off, such objects are worth registering as scene lights.
+    pub fn is_emissive(&self) -> bool {
+        let glow = match self {
+            Material::Pbr(p) => p.emission,
+            Material::DiffuseLight(l) => l.emit,
+            _ => return false,
+        };
+
+        glow.red.max(glow.green).max(glow.blue) > 0.0
+    }
+
+    // Same material with another base color, glass and lights have none and are returned unchanged.
+    pub fn with_albedo(self, albedo: Srgb) -> Material {
+        match self {
+            Material::Lambertian(_) => Material::Lambertian(Lambertian::new(albedo)),
+            Material::Metallic(m) => Material::Metallic(Metal::new(albedo, m.fuzz)),
+            Material::Pbr(p) => Material::Pbr(Pbr { base_color: albedo, ..p }),
+            Material::Glass(_) | Material::DiffuseLight(_) => self,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Lambertian {
@@ -43,9 +126,9 @@ impl Default for Lambertian {
 }
 
 impl Scatterable for Lambertian {
-    fn scatter(&self, _ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Option<(ray::Ray, Srgb)> {
+    fn scatter(&self, _ray: &ray::Ray, hit_record: &hit_record::HitRecord, sampler: &mut dyn Sampler) -> Option<(ray::Ray, Srgb, Option<f64>)> {
 
-        let mut scatter_direction = hit_record.normal + vector::Vec3::random_unit_vec3();
+        let mut scatter_direction = hit_record.normal + vector::Vec3::sample_unit_vec3(sampler.get_2d());
 
         if scatter_direction.near_zero() {
             scatter_direction = hit_record.normal;
@@ -54,8 +137,21 @@ impl Scatterable for Lambertian {
         let target_ray = hit_record.point + scatter_direction;
         let scattered = ray::Ray::new(hit_record.point, target_ray - hit_record.point);
         let attenuation = self.albedo;
+        let pdf = cosine_pdf(&hit_record.normal, &scattered.direction);
+
+        Some((scattered, attenuation, Some(pdf)))
+    }
+
+    fn scattering_pdf(&self, _ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> f64 {
+        cosine_pdf(&hit_record.normal, &direction)
+    }
 
-        Some((scattered, attenuation))
+    fn evaluate(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> Srgb {
+        self.albedo * self.scattering_pdf(ray, hit_record, direction) as f32
+    }
+
+    fn is_specular(&self) -> bool {
+        false
     }
 }
 
@@ -88,6 +184,69 @@ impl Glass {
     }
 }
 
+// Metallic-roughness material of glTF: metals reflect tinted by the base color,
+// other surfaces are diffuse with a white specular coat which gets stronger at grazing angles.
+#[derive(Debug, Clone, Copy)]
+pub struct Pbr {
+    pub base_color: Srgb,
+    pub metallic: f64,
+    pub roughness: f64,
+    // Light given off by the front side, black for most surfaces
+    pub emission: Srgb,
+}
+
+impl Pbr {
+    pub fn new(base_color: Srgb, metallic: f64, roughness: f64) -> Pbr {
+        Pbr { base_color, metallic: metallic.clamp(0.0, 1.0), roughness: roughness.clamp(0.0, 1.0), emission: Srgb::new(0.0, 0.0, 0.0) }
+    }
+
+    pub fn with_emission(mut self, emission: Srgb) -> Pbr {
+        self.emission = emission;
+        self
+    }
+
+    // Chance of picking the specular lobe, its mirror direction and fuzz radius for a ray hitting the surface.
+    fn specular_lobe(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> (f64, vector::Vec3, f64) {
+        let unit_vec_direction = ray.direction.unit_vector();
+        let cos_theta = (-unit_vec_direction).dot(&hit_record.normal).clamp(0.0, 1.0);
+        let fresnel = DIELECTRIC_REFLECTANCE + (1.0 - DIELECTRIC_REFLECTANCE) * (1.0 - cos_theta).powi(5);
+
+        let specular_weight = self.metallic + (1.0 - self.metallic) * fresnel;
+
+        (specular_weight, reflect(&unit_vec_direction, &hit_record.normal), self.roughness * self.roughness)
+    }
+
+    // Tint of the specular lobe, metals color their reflections and the dielectric coat is white.
+    fn specular_color(&self, specular_weight: f64) -> Srgb {
+        let metal_share = (self.metallic / specular_weight) as f32;
+
+        self.base_color * metal_share + Srgb::new(1.0, 1.0, 1.0) * (1.0 - metal_share)
+    }
+}
+
+// Area light, every point of its surface emits the same light in all directions.
+// Absorbs all light falling on it, so it looks the same in every scene.
+#[derive(Debug, Clone, Copy)]
+pub struct DiffuseLight {
+    pub emit: Srgb,
+    // Whether the back side glows too, only the front side (where the normal points) does by default
+    pub two_sided: bool,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Srgb) -> DiffuseLight {
+        DiffuseLight { emit, two_sided: false }
+    }
+
+    pub fn with_two_sided(mut self, two_sided: bool) -> DiffuseLight {
+        self.two_sided = two_sided;
+        self
+    }
+}
+
+// Reflectance of non-metals at normal incidence
+const DIELECTRIC_REFLECTANCE: f64 = 0.04;
+
 fn reflect(v: &vector::Vec3, n: &vector::Vec3) -> vector::Vec3 {
     *v - *n * (2.0 * v.dot(n))
 }
@@ -96,11 +255,42 @@ fn refract(v: &vector::Vec3, n: &vector::Vec3, eta_quotient: f64) -> vector::Vec
     let cos_theta = ((-*v).dot(n)).min(1.0);
     
     let r_out_perp = (*v + *n * cos_theta) * eta_quotient;
-    let r_out_parallel = *n * (-1.0 * (1.0 - r_out_perp.length_squared().abs().sqrt() ));
+    let r_out_parallel = *n * -(1.0 - r_out_perp.length_squared().abs().sqrt() );
 
     r_out_parallel + r_out_perp
 }
 
+// Density of a cosine weighted direction around the normal, as scattered by diffuse surfaces.
+fn cosine_pdf(normal: &vector::Vec3, direction: &vector::Vec3) -> f64 {
+    (normal.dot(&direction.unit_vector()) / std::f64::consts::PI).max(0.0)
+}
+
+// Density of `reflected + fuzz * uniform unit vector` pointing along `direction`, `reflected` is a unit vector.
+// A point picked on the fuzz sphere covers solid angle t^2 / |cos| times its area, where the direction meets the sphere at distance t.
+fn fuzzy_reflection_pdf(reflected: &vector::Vec3, fuzz: f64, direction: &vector::Vec3) -> f64 {
+    if fuzz <= 0.0 {
+        return 0.0
+    }
+
+    let w = direction.unit_vector();
+    let b = w.dot(reflected);
+    let discriminant = b * b - 1.0 + fuzz * fuzz;
+    if discriminant < 0.0 {
+        return 0.0
+    }
+
+    let root = discriminant.sqrt();
+    let mut pdf = 0.0;
+    for t in [b - root, b + root] {
+        let cosine = ((w * t - *reflected) / fuzz).dot(&w).abs();
+        if t > 0.0 && cosine > 1e-9 {
+            pdf += t * t / (cosine * 4.0 * std::f64::consts::PI * fuzz * fuzz);
+        }
+    }
+
+    pdf
+}
+
 fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
     let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
 
@@ -110,22 +300,39 @@ fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
 }
 
 impl Scatterable for Metal {
-    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Option<(ray::Ray, Srgb)> {
-        let reflected = reflect(&ray.direction, &hit_record.normal);
-        let scattered = ray::Ray::new(hit_record.point, reflected + vector::Vec3::random_unit_vec3() * self.fuzz);
+    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, sampler: &mut dyn Sampler) -> Option<(ray::Ray, Srgb, Option<f64>)> {
+        let reflected = reflect(&ray.direction.unit_vector(), &hit_record.normal);
+        let scattered = ray::Ray::new(hit_record.point, reflected + vector::Vec3::sample_unit_vec3(sampler.get_2d()) * self.fuzz);
         let attenuation = self.albedo;
         if scattered.direction.dot(&hit_record.normal) > 0.0 {
-            Some((scattered, attenuation))
+            let pdf = (!self.is_specular()).then(|| self.scattering_pdf(ray, hit_record, scattered.direction));
+            Some((scattered, attenuation, pdf))
         } else {
             None
         }
     }
+
+    fn scattering_pdf(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> f64 {
+        fuzzy_reflection_pdf(&reflect(&ray.direction.unit_vector(), &hit_record.normal), self.fuzz, &direction)
+    }
+
+    // Rays scattered below the surface are absorbed.
+    fn evaluate(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> Srgb {
+        if direction.dot(&hit_record.normal) <= 0.0 {
+            return Srgb::new(0.0, 0.0, 0.0)
+        }
+
+        self.albedo * self.scattering_pdf(ray, hit_record, direction) as f32
+    }
+
+    fn is_specular(&self) -> bool {
+        self.fuzz <= 0.0
+    }
 }
 
 impl Scatterable for Glass {
-    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Option<(ray::Ray, Srgb)> {
-        let mut rng = rand::thread_rng();
-        let attenuation = Srgb::new(1.0 as f32, 1.0 as f32, 1.0 as f32);
+    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, sampler: &mut dyn Sampler) -> Option<(ray::Ray, Srgb, Option<f64>)> {
+        let attenuation = Srgb::new(1.0_f32, 1.0_f32, 1.0_f32);
         let refraction_ratio = if hit_record.front_face {
             1.0 / self.refraction_index
         } else {
@@ -139,14 +346,88 @@ impl Scatterable for Glass {
         let can_refract = refraction_ratio * sin_theta > 1.0;
 
 
-        if can_refract || reflectance(cos_theta, refraction_ratio) > rng.gen::<f64>() {
+        if can_refract || reflectance(cos_theta, refraction_ratio) > sampler.get_1d() {
             let reflected = reflect(&unit_vec_direction, &hit_record.normal);
             let scattered = ray::Ray::new(hit_record.point, reflected);
-            Some((scattered, attenuation))
+            Some((scattered, attenuation, None))
         } else {
             let direction = refract(&unit_vec_direction, &hit_record.normal, refraction_ratio);
             let scattered = ray::Ray::new(hit_record.point, direction);
-            Some((scattered, attenuation))
+            Some((scattered, attenuation, None))
         }
     }
-}
\ No newline at end of file
+}
+
+impl Scatterable for Pbr {
+    fn scatter(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, sampler: &mut dyn Sampler) -> Option<(ray::Ray, Srgb, Option<f64>)> {
+        // One lobe is picked by its share of reflected light, its attenuation is divided by that probability.
+        let (specular_weight, reflected, fuzz) = self.specular_lobe(ray, hit_record);
+
+        if sampler.get_1d() < specular_weight {
+            let scattered = ray::Ray::new(hit_record.point, reflected + vector::Vec3::sample_unit_vec3(sampler.get_2d()) * fuzz);
+            if scattered.direction.dot(&hit_record.normal) <= 0.0 {
+                return None
+            }
+
+            // Without fuzz the lobe is a mirror, its direction has no density
+            let pdf = (fuzz > 0.0).then(|| self.scattering_pdf(ray, hit_record, scattered.direction));
+
+            Some((scattered, self.specular_color(specular_weight), pdf))
+        } else {
+            let mut scatter_direction = hit_record.normal + vector::Vec3::sample_unit_vec3(sampler.get_2d());
+            if scatter_direction.near_zero() {
+                scatter_direction = hit_record.normal;
+            }
+
+            let pdf = self.scattering_pdf(ray, hit_record, scatter_direction);
+
+            Some((ray::Ray::new(hit_record.point, scatter_direction), self.base_color, Some(pdf)))
+        }
+    }
+
+    fn emitted(&self, _ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Srgb {
+        if hit_record.front_face {
+            self.emission
+        } else {
+            Srgb::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    // Both lobes can scatter towards any direction, so the density is their mix.
+    // Without roughness the specular lobe is a single mirror direction with no density, only the diffuse share is left.
+    fn scattering_pdf(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> f64 {
+        let (specular_weight, reflected, fuzz) = self.specular_lobe(ray, hit_record);
+
+        specular_weight * fuzzy_reflection_pdf(&reflected, fuzz, &direction) + (1.0 - specular_weight) * cosine_pdf(&hit_record.normal, &direction)
+    }
+
+    fn evaluate(&self, ray: &ray::Ray, hit_record: &hit_record::HitRecord, direction: vector::Vec3) -> Srgb {
+        let (specular_weight, reflected, fuzz) = self.specular_lobe(ray, hit_record);
+        let diffuse = self.base_color * ((1.0 - specular_weight) * cosine_pdf(&hit_record.normal, &direction)) as f32;
+
+        if direction.dot(&hit_record.normal) <= 0.0 {
+            return diffuse
+        }
+
+        diffuse + self.specular_color(specular_weight) * (specular_weight * fuzzy_reflection_pdf(&reflected, fuzz, &direction)) as f32
+    }
+
+    // Only smooth pure metals have nothing but the mirror lobe, other surfaces still sample light for their diffuse lobe.
+    fn is_specular(&self) -> bool {
+        self.metallic >= 1.0 && self.roughness <= 0.0
+    }
+}
+
+impl Scatterable for DiffuseLight {
+    fn scatter(&self, _ray: &ray::Ray, _hit_record: &hit_record::HitRecord, _sampler: &mut dyn Sampler) -> Option<(ray::Ray, Srgb, Option<f64>)> {
+        None
+    }
+
+    fn emitted(&self, _ray: &ray::Ray, hit_record: &hit_record::HitRecord) -> Srgb {
+        if hit_record.front_face || self.two_sided {
+            self.emit
+        } else {
+            Srgb::new(0.0, 0.0, 0.0)
+        }
+    }
+}
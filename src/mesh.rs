@@ -0,0 +1,70 @@
+use std::{fmt, path::PathBuf};
+use crate::{ray, hit_record::HitRecord, aabb::Aabb, bvh::Bvh, triangle::Triangle, texture::PbrTextures, hittable::Hittable};
+
+// Named group of triangles loaded from a model file, kept in its own BVH.
+pub struct TriangleMesh{
+    name: String,
+    triangles: Bvh<Triangle>,
+    // Looked up only for the closest hit, so triangles can keep plain copyable materials
+    textures: PbrTextures,
+}
+
+impl TriangleMesh{
+    pub fn new(name: &str, triangles: Vec<Triangle>) -> TriangleMesh{
+        TriangleMesh { name: name.to_string(), triangles: Bvh::new(triangles), textures: PbrTextures::default() }
+    }
+
+    pub fn with_textures(mut self, textures: PbrTextures) -> TriangleMesh{
+        self.textures = textures;
+        self
+    }
+
+    pub fn name(&self) -> &str{
+        &self.name
+    }
+
+    pub fn len(&self) -> usize{
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.triangles.is_empty()
+    }
+
+    pub fn triangles(&self) -> &[Triangle]{
+        self.triangles.objects()
+    }
+}
+
+impl Hittable for TriangleMesh{
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut hit = self.triangles.hit(r, t_min, t_max)?;
+        if !self.textures.is_empty() {
+            hit.material = self.textures.apply(hit.material, hit.u, hit.v);
+        }
+
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.triangles.bounding_box()
+    }
+}
+
+#[derive(Debug)]
+pub enum MeshError{
+    Io(PathBuf, std::io::Error),
+    // File could be read, but its content is broken or unsupported
+    Format(PathBuf, String),
+}
+
+impl fmt::Display for MeshError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io(path, error) => write!(f, "failed to read model {}: {}", path.display(), error),
+            MeshError::Format(path, message) => write!(f, "failed to load model {}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
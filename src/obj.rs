@@ -0,0 +1,95 @@
+use std::{fs::File, io::BufReader, path::Path};
+use palette::Srgb;
+use crate::{vector::Vec3, triangle::Triangle, mesh::{TriangleMesh, MeshError}, material::{Material, Lambertian, Metal, Glass}};
+
+// Loads a Wavefront OBJ file, every object or group becomes its own mesh.
+// Faces are triangulated, materials come from the MTL library and faces without one get `default_material`.
+// A missing or broken library leaves every face without a material, the geometry is still loaded.
+pub fn load(path: &Path, default_material: Material) -> Result<Vec<TriangleMesh>, MeshError>{
+    let file = File::open(path).map_err(|error| MeshError::Io(path.to_path_buf(), error))?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+    };
+
+    let (models, materials) = tobj::load_obj_buf(&mut BufReader::new(file), &options, |library| {
+        tobj::load_mtl(directory.join(library))
+    }).map_err(|error| MeshError::Format(path.to_path_buf(), error.to_string()))?;
+
+    let materials: Vec<Material> = materials
+        .unwrap_or_default()
+        .iter()
+        .map(convert_material)
+        .collect();
+
+    let mut meshes = Vec::new();
+    for model in &models {
+        let mesh = &model.mesh;
+        let material = mesh.material_id.and_then(|id| materials.get(id)).copied().unwrap_or(default_material);
+
+        let vertex_count = mesh.positions.len() / 3;
+        let has_normals = mesh.normals.len() / 3 == vertex_count;
+        let has_uvs = mesh.texcoords.len() / 2 == vertex_count;
+
+        let position = |i: usize| Vec3::new(mesh.positions[3 * i] as f64, mesh.positions[3 * i + 1] as f64, mesh.positions[3 * i + 2] as f64);
+        let normal = |i: usize| Vec3::new(mesh.normals[3 * i] as f64, mesh.normals[3 * i + 1] as f64, mesh.normals[3 * i + 2] as f64);
+        let uv = |i: usize| (mesh.texcoords[2 * i] as f64, mesh.texcoords[2 * i + 1] as f64);
+
+        let mut triangles = Vec::with_capacity(mesh.indices.len() / 3);
+        for face in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [face[0] as usize, face[1] as usize, face[2] as usize];
+            if a.max(b).max(c) >= vertex_count {
+                return Err(MeshError::Format(path.to_path_buf(), format!("face of `{}` refers to missing vertex", model.name)));
+            }
+
+            let mut triangle = Triangle::new(position(a), position(b), position(c), material);
+            if has_normals {
+                triangle = triangle.with_normals([normal(a), normal(b), normal(c)]);
+            }
+            if has_uvs {
+                triangle = triangle.with_uvs([uv(a), uv(b), uv(c)]);
+            }
+
+            triangles.push(triangle);
+        }
+
+        if !triangles.is_empty() {
+            meshes.push(TriangleMesh::new(&model.name, triangles));
+        }
+    }
+
+    Ok(meshes)
+}
+
+// Closest of our materials: transparent ones become glass, shiny ones metal and the rest diffuse.
+fn convert_material(material: &tobj::Material) -> Material{
+    let to_color = |c: [f32; 3]| Srgb::new(c[0], c[1], c[2]);
+    let diffuse = material.diffuse.unwrap_or([0.8, 0.8, 0.8]);
+    let specular = material.specular.unwrap_or([0.0, 0.0, 0.0]);
+    let illumination = material.illumination_model.unwrap_or(2);
+
+    // Illumination models 4, 6, 7 and 9 are the transparent ones
+    if material.dissolve.is_some_and(|d| d < 1.0) || matches!(illumination, 4 | 6 | 7 | 9) {
+        let refraction_index = material.optical_density.filter(|ni| *ni > 0.0).unwrap_or(1.5);
+
+        return Material::Glass(Glass::new(refraction_index as f64))
+    }
+
+    // Model 3 turns on ray traced reflections, otherwise the specular color has to outweigh the diffuse one.
+    let brightness = |c: [f32; 3]| c[0].max(c[1]).max(c[2]);
+    if illumination == 3 || brightness(specular) > brightness(diffuse) {
+        // Phong exponent of 0 - 1000 turned into fuzz, large exponents give sharp reflections
+        let shininess = material.shininess.unwrap_or(0.0).max(0.0) as f64;
+        let fuzz = (2.0 / (shininess + 2.0)).sqrt().min(1.0);
+
+        let albedo = if brightness(specular) > 0.0 { specular } else { diffuse };
+
+        return Material::Metallic(Metal::new(to_color(albedo), fuzz))
+    }
+
+    Material::Lambertian(Lambertian::new(to_color(diffuse)))
+}
@@ -0,0 +1,76 @@
+use std::{io::{Cursor, Write}, path::Path};
+use image::{ImageBuffer, ImageOutputFormat, ImageResult, Rgb, codecs::pnm::{PnmSubtype, SampleEncoding}};
+use crate::{framebuffer::Framebuffer, interval, tonemap::OutputTransform};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    // JPEG with quality, up to 100
+    Jpeg(u8),
+    // Binary P6 PPM
+    Ppm,
+    // OpenEXR keeps linear float values without clamping
+    Exr,
+}
+
+impl OutputFormat {
+    // Picks the format based on file extension, e.g. "render.png"
+    pub fn from_path(path: &Path) -> Option<OutputFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "png" => Some(OutputFormat::Png),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg(90)),
+            "ppm" | "pnm" => Some(OutputFormat::Ppm),
+            "exr" => Some(OutputFormat::Exr),
+            _ => None,
+        }
+    }
+}
+
+// 8-bit formats go through the output transform, EXR keeps the linear values.
+pub fn write<W: Write>(framebuffer: &Framebuffer, format: OutputFormat, transform: &OutputTransform, writer: &mut W) -> ImageResult<()> {
+    // Some encoders need to seek, so image is encoded in memory first.
+    let mut encoded = Cursor::new(Vec::new());
+
+    match format {
+        OutputFormat::Png => to_rgb8(framebuffer, transform).write_to(&mut encoded, ImageOutputFormat::Png)?,
+        OutputFormat::Jpeg(quality) => to_rgb8(framebuffer, transform).write_to(&mut encoded, ImageOutputFormat::Jpeg(quality))?,
+        OutputFormat::Ppm => to_rgb8(framebuffer, transform).write_to(&mut encoded, ImageOutputFormat::Pnm(PnmSubtype::Pixmap(SampleEncoding::Binary)))?,
+        OutputFormat::Exr => to_rgb32f(framebuffer).write_to(&mut encoded, ImageOutputFormat::OpenExr)?,
+    }
+
+    writer.write_all(encoded.get_ref())?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn save(framebuffer: &Framebuffer, format: OutputFormat, transform: &OutputTransform, path: &Path) -> ImageResult<()> {
+    let mut file = std::fs::File::create(path)?;
+
+    write(framebuffer, format, transform, &mut file)
+}
+
+pub fn to_rgb8(framebuffer: &Framebuffer, transform: &OutputTransform) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    static I: f64 = 256.0;
+    let intensity = interval::Interval::new(0.000, 0.999);
+
+    ImageBuffer::from_fn(framebuffer.width(), framebuffer.height(), |x, y| {
+        let color = transform.apply(framebuffer.get(x, y));
+
+        Rgb([
+            (intensity.clamp(color.red) * I) as u8,
+            (intensity.clamp(color.green) * I) as u8,
+            (intensity.clamp(color.blue) * I) as u8,
+        ])
+    })
+}
+
+pub fn to_rgb32f(framebuffer: &Framebuffer) -> ImageBuffer<Rgb<f32>, Vec<f32>> {
+    ImageBuffer::from_fn(framebuffer.width(), framebuffer.height(), |x, y| {
+        let color = framebuffer.get(x, y);
+
+        Rgb([color.red, color.green, color.blue])
+    })
+}
@@ -0,0 +1,55 @@
+use crate::{ray, hit_record, vector, interval::Interval, aabb::Aabb, material::Material, hittable::Hittable};
+
+// Rays nearly parallel to a flat surface are treated as missing it
+pub const PARALLEL_EPSILON: f64 = 1e-8;
+
+// Infinite plane through `point`, facing the direction of `normal`.
+pub struct Plane{
+    point: vector::Vec3,
+    normal: vector::Vec3,
+    // Directions of the u and v texture coordinates along the plane
+    tangent: vector::Vec3,
+    bitangent: vector::Vec3,
+    material: Material,
+}
+
+impl Plane{
+    pub fn new(point: vector::Vec3, normal: vector::Vec3, material: Material) -> Plane {
+        let normal = normal.unit_vector();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+
+        Plane {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane{
+    fn hit(&self, ray: &ray::Ray, t_min: f64, t_max: f64) -> Option<hit_record::HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None
+        }
+
+        let t = self.normal.dot(&(self.point - ray.origin())) / denominator;
+        if t <= t_min || t >= t_max {
+            return None
+        }
+
+        // Texture coordinates are distances along the plane, so textures repeat every unit.
+        let offset = ray.at(t) - self.point;
+        let uv = (offset.dot(&self.tangent), offset.dot(&self.bitangent));
+
+        Some(hit_record::HitRecord::from_outward_normal(ray, t, self.normal, uv, self.material))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let infinite = Interval::new(f64::NEG_INFINITY, f64::INFINITY);
+
+        Aabb::new(infinite, infinite, infinite)
+    }
+}
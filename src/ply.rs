@@ -0,0 +1,416 @@
+use std::{fs, path::Path, str::SplitAsciiWhitespace};
+use palette::{Srgb, LinSrgb};
+use crate::{vector::Vec3, triangle::Triangle, mesh::{TriangleMesh, MeshError}, material::Material, stl::mesh_name};
+
+// Loads an ASCII or binary (either byte order) PLY file into one mesh named after the file.
+// Vertex normals give smooth shading and vertex colors replace the color of `material`, polygons are triangulated.
+pub fn load(path: &Path, material: Material) -> Result<TriangleMesh, MeshError>{
+    let data = fs::read(path).map_err(|error| MeshError::Io(path.to_path_buf(), error))?;
+
+    let triangles = parse(&data, material).map_err(|message| MeshError::Format(path.to_path_buf(), message))?;
+
+    Ok(TriangleMesh::new(&mesh_name(path), triangles))
+}
+
+// Triangles of PLY file content.
+pub fn parse(data: &[u8], material: Material) -> Result<Vec<Triangle>, String>{
+    let (header, body_start) = parse_header(data)?;
+    let mut body = match header.format {
+        Format::Ascii => {
+            let text = std::str::from_utf8(&data[body_start..]).map_err(|_| "ASCII body is not valid text".to_string())?;
+            Body::Ascii(text.split_ascii_whitespace())
+        }
+        Format::Binary { big_endian } => Body::Binary { data: &data[body_start..], position: 0, big_endian },
+    };
+
+    let mut vertices = Vertices::default();
+    let mut faces: Vec<Vec<usize>> = Vec::new();
+
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => vertices = read_vertices(&mut body, element)?,
+            "face" => faces = read_faces(&mut body, element)?,
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        body.skip(property)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let vertex_count = vertices.positions.len();
+    let mut triangles = Vec::with_capacity(faces.len());
+    for face in &faces {
+        if face.iter().any(|index| *index >= vertex_count) {
+            return Err("face refers to missing vertex".to_string())
+        }
+
+        // Polygons are split into a fan around their first vertex.
+        for i in 1..face.len().saturating_sub(1) {
+            let [a, b, c] = [face[0], face[i], face[i + 1]];
+
+            let mut triangle = Triangle::new(vertices.positions[a], vertices.positions[b], vertices.positions[c], material);
+            if let Some(normals) = &vertices.normals {
+                triangle = triangle.with_normals([normals[a], normals[b], normals[c]]);
+            }
+            if let Some(uvs) = &vertices.uvs {
+                triangle = triangle.with_uvs([uvs[a], uvs[b], uvs[c]]);
+            }
+            if let Some(colors) = &vertices.colors {
+                triangle = triangle.with_colors([colors[a], colors[b], colors[c]]);
+            }
+
+            triangles.push(triangle);
+        }
+    }
+
+    Ok(triangles)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format{
+    Ascii,
+    Binary { big_endian: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScalarType{
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType{
+    fn parse(name: &str) -> Option<ScalarType>{
+        match name {
+            "char" | "int8" => Some(ScalarType::Int8),
+            "uchar" | "uint8" => Some(ScalarType::UInt8),
+            "short" | "int16" => Some(ScalarType::Int16),
+            "ushort" | "uint16" => Some(ScalarType::UInt16),
+            "int" | "int32" => Some(ScalarType::Int32),
+            "uint" | "uint32" => Some(ScalarType::UInt32),
+            "float" | "float32" => Some(ScalarType::Float32),
+            "double" | "float64" => Some(ScalarType::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> usize{
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    // Smallest and largest value of integer types, None for floating point ones.
+    fn integer_range(&self) -> Option<(i64, i64)>{
+        match self {
+            ScalarType::Int8 => Some((i8::MIN as i64, i8::MAX as i64)),
+            ScalarType::UInt8 => Some((0, u8::MAX as i64)),
+            ScalarType::Int16 => Some((i16::MIN as i64, i16::MAX as i64)),
+            ScalarType::UInt16 => Some((0, u16::MAX as i64)),
+            ScalarType::Int32 => Some((i32::MIN as i64, i32::MAX as i64)),
+            ScalarType::UInt32 => Some((0, u32::MAX as i64)),
+            ScalarType::Float32 | ScalarType::Float64 => None,
+        }
+    }
+
+    // Largest value of unsigned types, colors stored in them are scaled by it.
+    fn max_value(&self) -> Option<f64>{
+        match self {
+            ScalarType::UInt8 => Some(u8::MAX as f64),
+            ScalarType::UInt16 => Some(u16::MAX as f64),
+            ScalarType::UInt32 => Some(u32::MAX as f64),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PropertyType{
+    Scalar(ScalarType),
+    // Count type and item type
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug, Clone)]
+struct Property{
+    name: String,
+    property_type: PropertyType,
+}
+
+#[derive(Debug, Clone)]
+struct Element{
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// Most records reserved up front, vectors grow past it as records are actually read.
+const MAX_RESERVED_RECORDS: usize = 1 << 16;
+
+impl Element{
+    // Room to reserve for the records, the count comes from the header and a broken file can claim billions.
+    fn reserved(&self, body: &Body) -> usize{
+        let available = match body {
+            Body::Binary { data, position, .. } => {
+                // Smallest record has every list empty
+                let record_size: usize = self.properties.iter().map(|property| match property.property_type {
+                    PropertyType::Scalar(scalar_type) | PropertyType::List(scalar_type, _) => scalar_type.size(),
+                }).sum();
+
+                (data.len() - position) / record_size.max(1)
+            }
+            Body::Ascii(_) => usize::MAX,
+        };
+
+        self.count.min(available).min(MAX_RESERVED_RECORDS)
+    }
+}
+
+struct Header{
+    format: Format,
+    elements: Vec<Element>,
+}
+
+// Header and the offset of the body which follows `end_header`.
+fn parse_header(data: &[u8]) -> Result<(Header, usize), String>{
+    if !data.starts_with(b"ply") {
+        return Err("not a PLY file".to_string())
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let end = data[position..].iter().position(|byte| *byte == b'\n').map(|offset| position + offset)
+            .ok_or_else(|| "header has no `end_header`".to_string())?;
+        let line = std::str::from_utf8(&data[position..end]).map_err(|_| "header is not valid text".to_string())?.trim();
+        position = end + 1;
+
+        let words: Vec<&str> = line.split_ascii_whitespace().collect();
+        match words.as_slice() {
+            ["ply"] => {}
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::Binary { big_endian: false },
+                    "binary_big_endian" => Format::Binary { big_endian: true },
+                    _ => return Err(format!("unknown format `{}`", kind)),
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| format!("invalid count of element `{}`", name))?;
+                elements.push(Element { name: name.to_string(), count, properties: Vec::new() });
+            }
+            ["property", "list", count_type, item_type, name] => {
+                let property_type = PropertyType::List(scalar_type(count_type)?, scalar_type(item_type)?);
+                add_property(&mut elements, name, property_type)?;
+            }
+            ["property", value_type, name] => {
+                add_property(&mut elements, name, PropertyType::Scalar(scalar_type(value_type)?))?;
+            }
+            ["end_header"] => break,
+            _ => return Err(format!("invalid header line `{}`", line)),
+        }
+    }
+
+    let format = format.ok_or_else(|| "header has no format".to_string())?;
+
+    Ok((Header { format, elements }, position))
+}
+
+fn scalar_type(name: &str) -> Result<ScalarType, String>{
+    ScalarType::parse(name).ok_or_else(|| format!("unknown property type `{}`", name))
+}
+
+fn add_property(elements: &mut [Element], name: &str, property_type: PropertyType) -> Result<(), String>{
+    let element = elements.last_mut().ok_or_else(|| format!("property `{}` comes before any element", name))?;
+    element.properties.push(Property { name: name.to_string(), property_type });
+
+    Ok(())
+}
+
+enum Body<'a>{
+    Ascii(SplitAsciiWhitespace<'a>),
+    Binary { data: &'a [u8], position: usize, big_endian: bool },
+}
+
+impl Body<'_>{
+    fn read(&mut self, scalar_type: ScalarType) -> Result<f64, String>{
+        match self {
+            Body::Ascii(words) => {
+                let word = words.next().ok_or_else(|| "file ends before all elements are read".to_string())?;
+
+                // Integer properties have to be written as integers which fit their type, like binary files store them
+                match scalar_type.integer_range() {
+                    Some((min, max)) => match word.parse::<i64>() {
+                        Ok(value) if (min..=max).contains(&value) => Ok(value as f64),
+                        _ => Err(format!("invalid {:?} number `{}`", scalar_type, word)),
+                    }
+                    None => word.parse().map_err(|_| format!("invalid number `{}`", word)),
+                }
+            }
+            Body::Binary { data, position, big_endian } => {
+                let size = scalar_type.size();
+                let bytes = data.get(*position..*position + size).ok_or_else(|| "file ends before all elements are read".to_string())?;
+                *position += size;
+
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(bytes);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+
+                Ok(match scalar_type {
+                    ScalarType::Int8 => buffer[0] as i8 as f64,
+                    ScalarType::UInt8 => buffer[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    ScalarType::Int32 => i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    ScalarType::UInt32 => u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    ScalarType::Float32 => f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    ScalarType::Float64 => f64::from_le_bytes(buffer),
+                })
+            }
+        }
+    }
+
+    fn read_list(&mut self, count_type: ScalarType, item_type: ScalarType) -> Result<Vec<f64>, String>{
+        let count = self.read(count_type)?;
+        if !(count >= 0.0 && count.fract() == 0.0) {
+            return Err(format!("invalid list length {}", count))
+        }
+
+        (0..count as usize).map(|_| self.read(item_type)).collect()
+    }
+
+    fn skip(&mut self, property: &Property) -> Result<(), String>{
+        match property.property_type {
+            PropertyType::Scalar(scalar_type) => self.read(scalar_type).map(|_| ()),
+            PropertyType::List(count_type, item_type) => self.read_list(count_type, item_type).map(|_| ()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Vertices{
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    colors: Option<Vec<Srgb>>,
+}
+
+fn read_vertices(body: &mut Body, element: &Element) -> Result<Vertices, String>{
+    // Index of the first property with one of the names, scanners and tools don't agree on them.
+    let find = |names: &[&str]| element.properties.iter().position(|property| {
+        names.contains(&property.name.as_str()) && matches!(property.property_type, PropertyType::Scalar(_))
+    });
+    let find_all = |names: [&[&str]; 3]| -> Option<[usize; 3]> {
+        Some([find(names[0])?, find(names[1])?, find(names[2])?])
+    };
+
+    let position = find_all([&["x"], &["y"], &["z"]]).ok_or_else(|| "vertices have no x, y and z".to_string())?;
+    let normal = find_all([&["nx"], &["ny"], &["nz"]]);
+    let color = find_all([&["red", "r", "diffuse_red"], &["green", "g", "diffuse_green"], &["blue", "b", "diffuse_blue"]]);
+    let uv = find(&["u", "s", "texture_u", "texture_s"]).zip(find(&["v", "t", "texture_v", "texture_t"]));
+
+    let reserved = element.reserved(body);
+    let mut vertices = Vertices {
+        positions: Vec::with_capacity(reserved),
+        normals: normal.map(|_| Vec::with_capacity(reserved)),
+        uvs: uv.map(|_| Vec::with_capacity(reserved)),
+        colors: color.map(|_| Vec::with_capacity(reserved)),
+    };
+
+    let mut values = vec![0.0; element.properties.len()];
+    for _ in 0..element.count {
+        for (value, property) in values.iter_mut().zip(&element.properties) {
+            match property.property_type {
+                PropertyType::Scalar(scalar_type) => *value = body.read(scalar_type)?,
+                PropertyType::List(..) => body.skip(property)?,
+            }
+        }
+
+        let vec3 = |[x, y, z]: [usize; 3]| Vec3::new(values[x], values[y], values[z]);
+
+        vertices.positions.push(vec3(position));
+        if let (Some(normals), Some(normal)) = (&mut vertices.normals, normal) {
+            normals.push(vec3(normal));
+        }
+        if let (Some(uvs), Some((u, v))) = (&mut vertices.uvs, uv) {
+            uvs.push((values[u], values[v]));
+        }
+        if let (Some(colors), Some(color)) = (&mut vertices.colors, color) {
+            colors.push(to_color(element, &values, color));
+        }
+    }
+
+    // Zero normals can't be used for shading, meshes with any of them are flat shaded.
+    if vertices.normals.as_ref().is_some_and(|normals| normals.iter().any(|normal| normal.length_squared() == 0.0)) {
+        vertices.normals = None;
+    }
+
+    Ok(vertices)
+}
+
+// Integer colors are gamma encoded and get decoded to linear, float colors are taken as linear.
+fn to_color(element: &Element, values: &[f64], indices: [usize; 3]) -> Srgb{
+    let channel = |index: usize| {
+        let PropertyType::Scalar(scalar_type) = element.properties[index].property_type else {
+            return 0.0
+        };
+
+        match scalar_type.max_value() {
+            Some(max) => {
+                let encoded = Srgb::new((values[index] / max) as f32, 0.0, 0.0);
+                let linear: LinSrgb = encoded.into_linear();
+                linear.red
+            }
+            None => values[index] as f32,
+        }
+    };
+
+    Srgb::new(channel(indices[0]), channel(indices[1]), channel(indices[2]))
+}
+
+fn read_faces(body: &mut Body, element: &Element) -> Result<Vec<Vec<usize>>, String>{
+    let indices = element.properties.iter().position(|property| {
+        matches!(property.name.as_str(), "vertex_indices" | "vertex_index") && matches!(property.property_type, PropertyType::List(..))
+    }).ok_or_else(|| "faces have no vertex_indices".to_string())?;
+
+    let mut faces = Vec::with_capacity(element.reserved(body));
+    for _ in 0..element.count {
+        let mut face = Vec::new();
+
+        for (i, property) in element.properties.iter().enumerate() {
+            match property.property_type {
+                PropertyType::List(count_type, item_type) if i == indices => {
+                    let values = body.read_list(count_type, item_type)?;
+                    // Floating point lists are allowed, but only with whole numbers
+                    if let Some(value) = values.iter().find(|value| !(**value >= 0.0 && value.fract() == 0.0)) {
+                        return Err(format!("invalid vertex index {}", value))
+                    }
+
+                    face = values.into_iter().map(|value| value as usize).collect();
+                }
+                _ => body.skip(property)?,
+            }
+        }
+
+        faces.push(face);
+    }
+
+    Ok(faces)
+}
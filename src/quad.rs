@@ -0,0 +1,58 @@
+use crate::{ray, hit_record, vector, aabb::Aabb, material::Material, hittable::Hittable, plane::PARALLEL_EPSILON};
+
+// Parallelogram with corner at `origin`, spanned by edges `u` and `v`, facing the direction of u x v.
+pub struct Quad{
+    origin: vector::Vec3,
+    u: vector::Vec3,
+    v: vector::Vec3,
+    normal: vector::Vec3,
+    // n / (n . n), projects hit points onto the edges
+    w: vector::Vec3,
+    material: Material,
+}
+
+impl Quad{
+    pub fn new(origin: vector::Vec3, u: vector::Vec3, v: vector::Vec3, material: Material) -> Quad {
+        let n = u.cross(&v);
+
+        Quad {
+            origin,
+            u,
+            v,
+            normal: n.unit_vector(),
+            w: n / n.dot(&n),
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad{
+    fn hit(&self, ray: &ray::Ray, t_min: f64, t_max: f64) -> Option<hit_record::HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None
+        }
+
+        let t = self.normal.dot(&(self.origin - ray.origin())) / denominator;
+        if t <= t_min || t >= t_max {
+            return None
+        }
+
+        // Hit point expressed in edge coordinates, both have to be within [0, 1] to lie inside of the quad.
+        let planar = ray.at(t) - self.origin;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None
+        }
+
+        Some(hit_record::HitRecord::from_outward_normal(ray, t, self.normal, (alpha, beta), self.material))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let diagonal = Aabb::from_points(self.origin, self.origin + self.u + self.v);
+        let other_diagonal = Aabb::from_points(self.origin + self.u, self.origin + self.v);
+
+        Aabb::surrounding(&diagonal, &other_diagonal).pad(0.0001)
+    }
+}
@@ -1,6 +1,8 @@
-use crate::{vector, interval,sphere::Sphere, hit_record, material::Scatterable};
+use crate::{vector, interval, hit_record, scene::Scene, material::Scatterable, sampler::Sampler};
 use palette::Srgb;
 
+// Number of bounces before paths can be terminated by russian roulette
+const ROULETTE_MIN_BOUNCES: u32 = 3;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
@@ -11,7 +13,7 @@ pub struct Ray {
 impl Ray{
     
     pub fn new(origin: vector::Vec3, direction: vector::Vec3) -> Ray{
-        Ray { origin: origin, direction: direction }
+        Ray { origin, direction }
     }
 
     pub fn at(&self, t: f64) -> vector::Vec3{
@@ -19,46 +21,144 @@ impl Ray{
     }
 
     pub fn origin(&self) -> vector::Vec3{
-        self.origin.clone()
+        self.origin
     }
 
     pub fn direction(&self) -> vector::Vec3{
-        self.direction.clone()
+        self.direction
     }
 
-    pub fn ray_color(ray: &Ray, intensity: interval::Interval, world: &Vec<Sphere>, depth: u32) -> Srgb {
-    
-        let hit = hit_record::HitRecord::hit_world(world, ray, intensity);
+    // Color seen along the ray, `depth` is the maximum number of bounces before the path is cut off.
+    pub fn ray_color(ray: &Ray, intensity: interval::Interval, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Srgb {
+        Ray::trace(ray, intensity, scene, depth, 0, None, sampler)
+    }
+
+    // `scattering_pdf` is the density the ray was scattered with, None for camera rays and mirror-like bounces
+    // whose light is never sampled directly.
+    // Non-specular hits aim shadow rays at the environment and at the scene lights (next event estimation),
+    // light found both ways is shared by multiple importance sampling.
+    fn trace(ray: &Ray, intensity: interval::Interval, scene: &Scene, depth: u32, bounce: u32, scattering_pdf: Option<f64>, sampler: &mut dyn Sampler) -> Srgb {
+
+        // Exceeded the ray bounce limit, no more light is gathered.
+        if depth == 0 {
+            return Srgb::new(0.0, 0.0, 0.0)
+        }
+
+        let hit = hit_record::HitRecord::hit_world(&scene.world, ray, intensity);
         match hit{
-            Some(HitRecord) => {
-                let scattered = HitRecord.material.scatter(ray, &HitRecord);
+            Some(hit_record) => {
+                let mut emitted = hit_record.material.emitted(ray, &hit_record);
+                if let Some(scattering_pdf) = scattering_pdf {
+                    if !scene.lights.is_empty() && emitted.red.max(emitted.green).max(emitted.blue) > 0.0 {
+                        emitted *= power_heuristic(scattering_pdf, scene.light_pdf(ray.origin(), ray.direction())) as f32;
+                    }
+                }
+
+                // Light given off here plus the light sampled directly, which mirrors and glass can't use.
+                let specular = hit_record.material.is_specular();
+                if !specular {
+                    // Scattered rays of the last bounce aren't traced, so they can't take a share of the light.
+                    let last_bounce = depth == 1;
+                    emitted += Ray::sample_environment(ray, &hit_record, intensity, scene, last_bounce, sampler);
+                    emitted += Ray::sample_lights(ray, &hit_record, intensity, scene, last_bounce, sampler);
+                }
+
+                let scattered = hit_record.material.scatter(ray, &hit_record, sampler);
             
                 match scattered {
-                    Some((scattered_ray, albedo)) => {
-                        let target_color = Ray::ray_color(&scattered_ray, intensity, world, depth);
+                    Some((scattered_ray, mut albedo, pdf)) => {
+
+                        // Russian roulette, dim paths are randomly terminated and survivors are boosted to stay unbiased.
+                        if bounce >= ROULETTE_MIN_BOUNCES {
+                            let survival = albedo.red.max(albedo.green).max(albedo.blue).clamp(0.05, 1.0);
+
+                            if sampler.get_1d() as f32 > survival {
+                                return emitted
+                            }
+                            albedo /= survival;
+                        }
+
+                        let target_color = Ray::trace(&scattered_ray, intensity, scene, depth - 1, bounce + 1, pdf, sampler);
     
-                        return Srgb::new(
-                            albedo.red * target_color.red,
-                            albedo.green * target_color.green,
-                            albedo.blue * target_color.blue,
-                        ); 
-                    }
-                    None => {
-                        return Srgb::new(0.0, 0.0, 0.0)
+                        Srgb::new(
+                            emitted.red + albedo.red * target_color.red,
+                            emitted.green + albedo.green * target_color.green,
+                            emitted.blue + albedo.blue * target_color.blue,
+                        )
                     }
+                    None => emitted,
                 }
             }
             None => {
-                let t: f32 = 0.5 * (ray.direction().unit_vector().y() as f32 + 1.0);
-    
-                return Srgb::new(
-                    (1.0 - t) * 1.0 + t * 0.5,
-                    (1.0 - t) * 1.0 + t * 0.7,
-                    (1.0 - t) * 1.0 + t * 1.0,
-                );
+                let color = scene.environment.color(ray.direction());
+
+                // Environment light is also found by sampling it, the two ways share it by multiple importance sampling.
+                match (scattering_pdf, scene.environment.pdf(ray.direction())) {
+                    (Some(scattering_pdf), Some(light_pdf)) => color * power_heuristic(scattering_pdf, light_pdf) as f32,
+                    _ => color,
+                }
             }
         }
-    
-    
     }
+
+    // Light arriving straight from a direction picked on the environment, zero when it's blocked or the environment isn't sampled.
+    fn sample_environment(ray: &Ray, hit_record: &hit_record::HitRecord, intensity: interval::Interval, scene: &Scene, last_bounce: bool, sampler: &mut dyn Sampler) -> Srgb {
+        let black = Srgb::new(0.0, 0.0, 0.0);
+
+        let Some((direction, light, light_pdf)) = scene.environment.sample(sampler.get_2d()) else {
+            return black
+        };
+
+        let reflected = hit_record.material.evaluate(ray, hit_record, direction);
+        if light_pdf <= 0.0 || reflected.red.max(reflected.green).max(reflected.blue) <= 0.0 {
+            return black
+        }
+
+        let shadow_ray = Ray::new(hit_record.point, direction);
+        if hit_record::HitRecord::hit_world(&scene.world, &shadow_ray, intensity).is_some() {
+            return black
+        }
+
+        let weight = if last_bounce { 1.0 } else { power_heuristic(light_pdf, hit_record.material.scattering_pdf(ray, hit_record, direction)) };
+
+        reflected * light * (weight / light_pdf) as f32
+    }
+
+    // Light arriving from a point picked on a random scene light, zero when something dark is in the way.
+    fn sample_lights(ray: &Ray, hit_record: &hit_record::HitRecord, intensity: interval::Interval, scene: &Scene, last_bounce: bool, sampler: &mut dyn Sampler) -> Srgb {
+        let black = Srgb::new(0.0, 0.0, 0.0);
+        if scene.lights.is_empty() {
+            return black
+        }
+
+        let index = ((sampler.get_1d() * scene.lights.len() as f64) as usize).min(scene.lights.len() - 1);
+        let Some((direction, _)) = scene.lights[index].sample(hit_record.point, sampler.get_2d()) else {
+            return black
+        };
+
+        let reflected = hit_record.material.evaluate(ray, hit_record, direction);
+        if reflected.red.max(reflected.green).max(reflected.blue) <= 0.0 {
+            return black
+        }
+
+        // Whatever the shadow ray meets gives the light, so lights in front of each other are handled too.
+        let shadow_ray = Ray::new(hit_record.point, direction);
+        let Some(light_hit) = hit_record::HitRecord::hit_world(&scene.world, &shadow_ray, intensity) else {
+            return black
+        };
+        let light = light_hit.material.emitted(&shadow_ray, &light_hit);
+
+        // Any light could have picked the direction, so the density is the one of all lights together.
+        let light_pdf = scene.light_pdf(hit_record.point, direction);
+        let weight = if last_bounce { 1.0 } else { power_heuristic(light_pdf, hit_record.material.scattering_pdf(ray, hit_record, direction)) };
+
+        reflected * light * (weight / light_pdf) as f32
+    }
+}
+
+// Weight of a sample taken with density `pdf` when another strategy could have taken it with `other_pdf`.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (pdf, other_pdf) = (pdf * pdf, other_pdf * other_pdf);
+
+    if pdf + other_pdf > 0.0 { pdf / (pdf + other_pdf) } else { 0.0 }
 }
\ No newline at end of file
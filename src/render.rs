@@ -1,74 +1,94 @@
-use rand::{Rng, rngs::ThreadRng};
-use palette::Srgb;
-use crate::{vector, interval, sphere::Sphere, ray, camera::Camera, material::{*, self}};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rayon::prelude::*;
+use crate::{interval, ray, camera::Camera, scene::Scene, framebuffer::Framebuffer, film::{Film, FilmTile}, sampler::Sampler};
+
+// Number of scanlines rendered together into one film tile
+const BAND_HEIGHT: u32 = 16;
 
 #[derive(Debug)]
 pub struct Render {}
 
 impl Render{
 
-    pub fn render(camera: Camera){
-        static I: f64 = 255.999;
+    // Renders the scene and returns filtered linear colors for every pixel.
+    pub fn render_to_buffer(scene: &Scene, camera: &Camera) -> Framebuffer {
+        Render::render_scanlines(scene, camera, false)
+    }
 
-        let intensity = interval::Interval::new(0.001, std::f64::MAX);
+    // Same as render_to_buffer, but reports remaining scanlines on stderr.
+    pub fn render_to_buffer_with_progress(scene: &Scene, camera: &Camera) -> Framebuffer {
+        Render::render_scanlines(scene, camera, true)
+    }
 
-        println!("P3");
-        println!("{} {}", camera.image_height, camera.image_width);
-        println!("{}", camera.image_height-1);
+    // Bands of scanlines are rendered in parallel on all CPU cores into their own film tiles,
+    // tiles are merged in band order afterwards so the image doesn't depend on thread scheduling.
+    fn render_scanlines(scene: &Scene, camera: &Camera, progress: bool) -> Framebuffer {
+        let mut film = Film::new(camera.image_width as u32, camera.image_height as u32, camera.filter);
 
-        let samples_per_pixel = 32;
-        let mut random: ThreadRng = rand::thread_rng();
+        let height = film.height();
+        let remaining = AtomicUsize::new(height as usize);
 
+        let tiles: Vec<FilmTile> = (0..height.div_ceil(BAND_HEIGHT)).into_par_iter().map(|band| {
+            let y_start = band * BAND_HEIGHT;
+            let y_end = (y_start + BAND_HEIGHT).min(height);
+            let mut tile = film.tile(y_start, y_end);
 
-        let mut world: Vec<Sphere> = Vec::new();
+            for y in y_start..y_end {
+                Render::render_scanline(scene, camera, y, &mut tile);
 
+                let left = remaining.fetch_sub(1, Ordering::Relaxed) - 1;
+                if progress {
+                    eprint!("\rScanlines remaining: {} ", left);
+                }
+            }
 
-        //Materials
-        let metal_sphere = material::Material::Metallic(Metal::new(Srgb::new(0.5, 0.5, 0.5), 0.0));
-        let fuzz_metal_sphere = material::Material::Metallic(Metal::new(Srgb::new(0.5, 0.0, 0.9), 0.3));
-        let normal_sphere = material::Material::Lambertian(Lambertian::new(Srgb::new(0.5, 0.5, 0.5)));
-        let glass_sphere = material::Material::Glass(Glass::new(3.0));
+            tile
+        }).collect();
 
-        //Spheres
-        world.push(Sphere::new(vector::Vec3::new(0.0, 0.0, -2.0), 0.5, normal_sphere));
-        world.push(Sphere::new(vector::Vec3::new(0.0, -100.5, -3.0), 100.0, normal_sphere));
-        world.push(Sphere::new(vector::Vec3::new(1.0, 0.0, -2.3), 0.5, metal_sphere));
-        world.push(Sphere::new(vector::Vec3::new(0.7, -0.3, -1.4), 0.2, fuzz_metal_sphere));
-        world.push(Sphere::new(vector::Vec3::new(-0.7, -0.3, -1.4), 0.2, glass_sphere));
+        for tile in &tiles {
+            film.merge_tile(tile);
+        }
 
+        if progress {
+            eprint!("\nDone                    \n");
+        }
 
-        for y in 0..camera.image_height {
-            eprint!("\rScanlines remaining: {} ", camera.image_height - y);
-            for x in 0..camera.image_width{
+        film.to_framebuffer()
+    }
 
-            let mut color = Srgb::new(0.0, 0.0, 0.0);
-            for _z in 0..samples_per_pixel{
+    fn render_scanline(scene: &Scene, camera: &Camera, y: u32, tile: &mut FilmTile) {
+        let intensity = interval::Interval::new(0.001, f64::MAX);
 
-                let pixel_center = camera.pixel00_loc + (camera.pixel_vec_u * x as f64) + (camera.pixel_vec_v * y as f64);
-                let pixel_sample = pixel_center + Render::pixel_sample_square(random.gen::<f64>(), camera.pixel_vec_u, camera.pixel_vec_v);
+        let samples_per_pixel = camera.samples_per_pixel;
+        let mut sampler = camera.sampler.create(camera.seed, samples_per_pixel);
 
-                let ray_direction = pixel_sample - camera.center;
+        for x in 0..camera.image_width as u32 {
+            for index in 0..samples_per_pixel{
+                // Sample values only depend on seed, pixel and sample index, not on which thread renders the pixel.
+                sampler.start_sample(x, y, index);
 
-                let r = ray::Ray::new(camera.center,   ray_direction);
-                color = color + ray::Ray::ray_color(&r, intensity, &world, camera.max_depth);    
+                let pixel_sample = sampler.get_2d();
+                let film_x = x as f64 + pixel_sample.0;
+                let film_y = y as f64 + pixel_sample.1;
 
-               }
-               // new func for Interval -> line too long 
-               println!("{} {} {}", (intensity.sample(color.red, samples_per_pixel) * I) as i32, (intensity.sample(color.green, samples_per_pixel)* I) as i32, (intensity.sample(color.blue, samples_per_pixel)* I) as i32);
+                let r = Render::get_ray(camera, film_x, film_y, sampler.as_mut());
+                let color = ray::Ray::ray_color(&r, intensity, scene, camera.max_depth, sampler.as_mut());
 
+                tile.add_sample(film_x, film_y, color);
             }
         }
-
-
-        eprint!("\nDone                    \n")
     }
 
-    fn pixel_sample_square(rng: f64, pixel_delta_u: vector::Vec3, pixel_delta_v: vector::Vec3) -> vector::Vec3 {
+    // Camera ray through continuous film position (x, y), starting on the defocus disk.
+    fn get_ray(camera: &Camera, x: f64, y: f64, sampler: &mut dyn Sampler) -> ray::Ray {
+        // pixel00_loc is the center of the first pixel, which sits at (0.5, 0.5) on the film.
+        let pixel_sample = camera.pixel00_loc + (camera.pixel_vec_u * (x - 0.5)) + (camera.pixel_vec_v * (y - 0.5));
 
-        let px = -0.5 + rng;
-        let py = -0.5 + rng;
+        let lens_sample = sampler.get_2d();
+        let ray_origin = if camera.defocus_angle <= 0.0 { camera.center } else { camera.defocus_disk_sample(lens_sample) };
+        let ray_direction = pixel_sample - ray_origin;
 
-        (pixel_delta_u * px) + (pixel_delta_v * py)
-    }  
+        ray::Ray::new(ray_origin,   ray_direction)
+    }
 
-}
\ No newline at end of file
+}
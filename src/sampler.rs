@@ -0,0 +1,354 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+
+// Source of sample values in [0, 1) for pixel positions, lens positions and scattering.
+// Each sample of a pixel is a point in many dimensions, values are handed out one dimension after another.
+pub trait Sampler: Send {
+    // Prepares sample `index` of pixel (x, y), following calls start again from the first dimension.
+    fn start_sample(&mut self, x: u32, y: u32, index: u32);
+
+    fn get_1d(&mut self) -> f64;
+
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplerType {
+    // Uncorrelated random numbers
+    Independent,
+    // Jittered samples, one in each cell of a grid over the pixel
+    Stratified,
+    // Halton sequence, Owen scrambled differently for every pixel
+    Halton,
+    // Owen scrambled Sobol (0,2) sequence, padded with shuffled pairs of dimensions
+    Sobol,
+}
+
+impl SamplerType {
+    pub fn create(&self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerType::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerType::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerType::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerType::Sobol => Box::new(SobolSampler::new(seed, samples_per_pixel)),
+        }
+    }
+}
+
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Pcg64Mcg,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> IndependentSampler {
+        IndependentSampler { seed, rng: Pcg64Mcg::seed_from_u64(seed) }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.rng = Pcg64Mcg::seed_from_u64(hash(&[self.seed, x as u64, y as u64, index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.gen(), self.rng.gen())
+    }
+}
+
+pub struct StratifiedSampler {
+    seed: u64,
+    // Size of the strata grid, x_strata * y_strata == samples per pixel
+    x_strata: u32,
+    y_strata: u32,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u64,
+    rng: Pcg64Mcg,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> StratifiedSampler {
+        let samples_per_pixel = samples_per_pixel.max(1);
+
+        // Grid as close to square as possible, e.g. 32 samples -> 4x8
+        let mut x_strata = (samples_per_pixel as f64).sqrt() as u32;
+        while !samples_per_pixel.is_multiple_of(x_strata) {
+            x_strata -= 1;
+        }
+
+        StratifiedSampler {
+            seed,
+            x_strata,
+            y_strata: samples_per_pixel / x_strata,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            rng: Pcg64Mcg::seed_from_u64(seed),
+        }
+    }
+
+    fn samples_per_pixel(&self) -> u32 {
+        self.x_strata * self.y_strata
+    }
+
+    // Every dimension visits strata in its own random order, so dimensions are not correlated.
+    fn stratum(&mut self) -> u32 {
+        let permutation = hash(&[self.seed, self.pixel.0 as u64, self.pixel.1 as u64, self.dimension]) as u32;
+        self.dimension += 1;
+
+        permute(self.index % self.samples_per_pixel(), self.samples_per_pixel(), permutation)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = (x, y);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = Pcg64Mcg::seed_from_u64(hash(&[self.seed, x as u64, y as u64, index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.stratum();
+
+        ((stratum as f64 + self.rng.gen::<f64>()) / self.samples_per_pixel() as f64).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let stratum = self.stratum();
+        let x = stratum % self.x_strata;
+        let y = stratum / self.x_strata;
+
+        (
+            ((x as f64 + self.rng.gen::<f64>()) / self.x_strata as f64).min(ONE_MINUS_EPSILON),
+            ((y as f64 + self.rng.gen::<f64>()) / self.y_strata as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: usize,
+    rng: Pcg64Mcg,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> HaltonSampler {
+        HaltonSampler { seed, pixel: (0, 0), index: 0, dimension: 0, rng: Pcg64Mcg::seed_from_u64(seed) }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = (x, y);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = Pcg64Mcg::seed_from_u64(hash(&[self.seed, x as u64, y as u64, index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        // Sequence runs out of prime bases for very long paths, remaining dimensions are random.
+        if dimension >= PRIMES.len() {
+            return self.rng.gen();
+        }
+
+        // Scrambling decorrelates neighbouring pixels without breaking stratification
+        let scramble = hash(&[self.seed, self.pixel.0 as u64, self.pixel.1 as u64, dimension as u64]);
+
+        owen_scrambled_radical_inverse(PRIMES[dimension], self.index as u64, scramble)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+pub struct SobolSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> SobolSampler {
+        SobolSampler { seed, samples_per_pixel: samples_per_pixel.max(1), pixel: (0, 0), index: 0, dimension: 0 }
+    }
+
+    // Every pair of dimensions uses the same 2D Sobol points, visited in a different order and differently scrambled.
+    fn next_hash(&mut self) -> u64 {
+        let dimension_hash = hash(&[self.seed, self.pixel.0 as u64, self.pixel.1 as u64, self.dimension]);
+        self.dimension += 1;
+
+        dimension_hash
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = (x, y);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension_hash = self.next_hash();
+        let index = permute(self.index % self.samples_per_pixel, self.samples_per_pixel, dimension_hash as u32);
+
+        to_unit_u32(owen_scramble(index.reverse_bits(), (dimension_hash >> 32) as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension_hash = self.next_hash();
+        let index = permute(self.index % self.samples_per_pixel, self.samples_per_pixel, dimension_hash as u32);
+        let second_hash = hash(&[dimension_hash]);
+
+        (
+            to_unit_u32(owen_scramble(index.reverse_bits(), (dimension_hash >> 32) as u32)),
+            to_unit_u32(owen_scramble(sobol_second_dimension(index), second_hash as u32)),
+        )
+    }
+}
+
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53,
+    59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131,
+];
+
+// Digits of `index` in `base` mirrored around the decimal point
+pub fn radical_inverse(base: u64, mut index: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut reversed = 0;
+    let mut inverse_base_n = 1.0;
+
+    while index > 0 {
+        let next = index / base;
+        let digit = index - next * base;
+        reversed = reversed * base + digit;
+        inverse_base_n *= inverse_base;
+        index = next;
+    }
+
+    (reversed as f64 * inverse_base_n).min(ONE_MINUS_EPSILON)
+}
+
+// Radical inverse with every digit permuted by a permutation chosen from the preceding digits
+fn owen_scrambled_radical_inverse(base: u64, mut index: u64, scramble: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    // Digits seen so far, only used to pick permutation of the next digit
+    let mut prefix: u64 = 0;
+    let mut inverse_base_n = 1.0;
+    let mut value = 0.0;
+
+    // Digits are generated until they no longer change the result, trailing zero digits get scrambled too.
+    while 1.0 - (base - 1) as f64 * inverse_base_n < 1.0 {
+        let next = index / base;
+        let digit = permute((index - next * base) as u32, base as u32, mix64(scramble ^ prefix) as u32) as u64;
+
+        prefix = prefix.wrapping_mul(base).wrapping_add(digit);
+        inverse_base_n *= inverse_base;
+        value += digit as f64 * inverse_base_n;
+        index = next;
+    }
+
+    value.min(ONE_MINUS_EPSILON)
+}
+
+// Second dimension of the Sobol sequence, the first one is just reversed bits of the index.
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut v = 1 << 31;
+    let mut result = 0;
+
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+
+    result
+}
+
+// Nested uniform scrambling of bits, keeps the stratification of Sobol points (Laine-Karras style hash).
+fn owen_scramble(mut v: u32, seed: u32) -> u32 {
+    v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+
+    v.reverse_bits()
+}
+
+// Element `i` of a random permutation of 0..length chosen by `p` (Kensler, "Correlated Multi-Jittered Sampling").
+fn permute(mut i: u32, length: u32, p: u32) -> u32 {
+    if length <= 1 {
+        return 0;
+    }
+
+    let mut w = length - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | (p >> 27));
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+
+        if i < length {
+            break;
+        }
+    }
+
+    (i.wrapping_add(p)) % length
+}
+
+// Combines values into one well mixed 64-bit hash
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x2545_F491_4F6C_DD1D, |h, v| mix64(h ^ mix64(*v)))
+}
+
+// SplitMix64 finalizer, scrambles bits so nearby inputs give unrelated outputs
+pub fn mix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn to_unit_u32(v: u32) -> f64 {
+    v as f64 / 4_294_967_296.0
+}
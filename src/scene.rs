@@ -0,0 +1,57 @@
+use palette::Srgb;
+use crate::{vector, environment::Environment, light::Light, sphere::Sphere, plane::Plane, bvh::Bvh, hittable::{Hittable, HittableList}, material::{*, self}};
+
+// Everything that can be hit by rays, objects of any kind are kept together in a BVH
+pub struct Scene{
+    pub world: Bvh<Box<dyn Hittable>>,
+    // Light of rays leaving the scene
+    pub environment: Environment,
+    // Glowing objects which shadow rays are aimed at, others are only found by rays bouncing into them
+    pub lights: Vec<Light>,
+}
+
+impl Scene{
+    pub fn new(world: HittableList) -> Scene{
+        Scene { world: Bvh::new(world.into_objects()), environment: Environment::sky(), lights: Vec::new() }
+    }
+
+    pub fn with_environment(mut self, environment: Environment) -> Scene{
+        self.environment = environment;
+        self
+    }
+
+    // Registers the shape of a glowing object of the world, it has to be added to the world too.
+    pub fn with_light(mut self, light: Light) -> Scene{
+        self.lights.push(light);
+        self
+    }
+
+    // Density of `direction` from `origin` when shadow rays are aimed at a light picked at random.
+    pub fn light_pdf(&self, origin: vector::Vec3, direction: vector::Vec3) -> f64{
+        if self.lights.is_empty() {
+            return 0.0
+        }
+
+        self.lights.iter().map(|light| light.pdf(origin, direction)).sum::<f64>() / self.lights.len() as f64
+    }
+
+    // Small scene with ground and one sphere of each material
+    pub fn demo() -> Scene{
+        let mut world = HittableList::new();
+
+        //Materials
+        let metal_sphere = material::Material::Metallic(Metal::new(Srgb::new(0.5, 0.5, 0.5), 0.0));
+        let fuzz_metal_sphere = material::Material::Metallic(Metal::new(Srgb::new(0.5, 0.0, 0.9), 0.3));
+        let normal_sphere = material::Material::Lambertian(Lambertian::new(Srgb::new(0.5, 0.5, 0.5)));
+        let glass_sphere = material::Material::Glass(Glass::new(3.0));
+
+        //Objects
+        world.add(Sphere::new(vector::Vec3::new(0.0, 0.0, -2.0), 0.5, normal_sphere));
+        world.add(Plane::new(vector::Vec3::new(0.0, -0.5, 0.0), vector::Vec3::new(0.0, 1.0, 0.0), normal_sphere));
+        world.add(Sphere::new(vector::Vec3::new(1.0, 0.0, -2.3), 0.5, metal_sphere));
+        world.add(Sphere::new(vector::Vec3::new(0.7, -0.3, -1.4), 0.2, fuzz_metal_sphere));
+        world.add(Sphere::new(vector::Vec3::new(-0.7, -0.3, -1.4), 0.2, glass_sphere));
+
+        Scene::new(world)
+    }
+}
@@ -0,0 +1,592 @@
+use std::{collections::{BTreeMap, HashMap}, fmt, fs, path::{Path, PathBuf}, sync::Arc};
+use palette::Srgb;
+use serde::Deserialize;
+use toml::Spanned;
+use crate::{vector::{Vec3, Transform}, environment::{Environment, EnvironmentMap}, light::Light, instance::Instance, bvh::Bvh, mesh::TriangleMesh, sphere::Sphere, plane::Plane, disk::Disk, quad::Quad, triangle::Triangle, obj, ply, stl, gltf_file, mesh::MeshError, hittable::{Hittable, HittableList}, scene::Scene, camera::{Camera, CameraBuilder, CameraError}, sampler::SamplerType, filter::FilterType, material::{Material, Lambertian, Metal, Glass, Pbr, DiffuseLight}};
+
+// Scene and camera described by a TOML scene file, camera is left as a builder so settings can still be overridden.
+pub struct SceneDescription{
+    pub scene: Scene,
+    pub camera: CameraBuilder,
+}
+
+#[derive(Debug)]
+pub enum SceneError{
+    Io(PathBuf, std::io::Error),
+    // Syntax or type error, toml reports it together with the offending line
+    Parse(toml::de::Error),
+    // glTF file given as the scene couldn't be loaded
+    Mesh(MeshError),
+    // Camera of a glTF file doesn't describe a view
+    Camera(CameraError),
+    // File is valid TOML, but describes something we can't build
+    Invalid{ line: usize, column: usize, source_line: String, message: String },
+}
+
+impl fmt::Display for SceneError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(path, error) => write!(f, "failed to read scene file {}: {}", path.display(), error),
+            SceneError::Parse(error) => write!(f, "{}", error),
+            SceneError::Mesh(error) => write!(f, "{}", error),
+            SceneError::Camera(error) => write!(f, "{}", error),
+            SceneError::Invalid { line, column, source_line, message } => {
+                writeln!(f, "scene error at line {}, column {}", line, column)?;
+                writeln!(f, "{:>4} | {}", line, source_line)?;
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile{
+    camera: Spanned<CameraDesc>,
+    // Defaults of RenderDesc when omitted
+    render: Option<Spanned<RenderDesc>>,
+    // Sky gradient when omitted
+    environment: Option<Spanned<EnvironmentDesc>>,
+    #[serde(default)]
+    materials: BTreeMap<String, Spanned<MaterialDesc>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectDesc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc{
+    width: i32,
+    height: Option<i32>,
+    aspect_ratio: Option<f64>,
+    look_from: Option<[f64; 3]>,
+    look_at: Option<[f64; 3]>,
+    vup: Option<[f64; 3]>,
+    vfov: Option<f64>,
+    defocus_angle: Option<f64>,
+    focus_dist: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+struct RenderDesc{
+    samples_per_pixel: u32,
+    max_depth: u32,
+    seed: u64,
+    sampler: SamplerDesc,
+    filter: FilterDesc,
+    filter_radius: Option<f64>,
+}
+
+impl Default for RenderDesc{
+    fn default() -> Self {
+        RenderDesc { samples_per_pixel: 32, max_depth: 50, seed: 0, sampler: SamplerDesc::Sobol, filter: FilterDesc::Box, filter_radius: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SamplerDesc{
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FilterDesc{
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum MaterialDesc{
+    Lambertian{ albedo: [f32; 3] },
+    Metal{ albedo: [f32; 3], #[serde(default)] fuzz: f64 },
+    Glass{ refraction_index: f64 },
+    // Metallic-roughness material, same as in glTF
+    Pbr{ base_color: [f32; 3], #[serde(default)] metallic: f64, #[serde(default = "default_roughness")] roughness: f64, emission: Option<[f32; 3]> },
+    // Area light, emit can go above 1 for bright lights
+    Light{ emit: [f32; 3], #[serde(default)] two_sided: bool },
+}
+
+fn default_roughness() -> f64{
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum EnvironmentDesc{
+    Constant{ color: [f32; 3] },
+    Gradient{ bottom: [f32; 3], top: [f32; 3] },
+    // Equirectangular .hdr or .exr image relative to the scene file, rotated around the vertical axis in degrees
+    Map{ path: PathBuf, #[serde(default)] rotation: f64, #[serde(default = "default_intensity")] intensity: f32 },
+}
+
+fn default_intensity() -> f32{
+    1.0
+}
+
+// Applied in this order: scale, rotations around the x, y and z axes in degrees and translation
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDesc{
+    scale: Option<ScaleDesc>,
+    rotate: Option<[f64; 3]>,
+    translate: Option<[f64; 3]>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ScaleDesc{
+    Uniform(f64),
+    Axes([f64; 3]),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum ObjectDesc{
+    Sphere{ center: [f64; 3], radius: f64, material: String, transform: Option<TransformDesc> },
+    Plane{ point: [f64; 3], normal: [f64; 3], material: String, transform: Option<TransformDesc> },
+    Disk{ center: [f64; 3], normal: [f64; 3], radius: f64, material: String, transform: Option<TransformDesc> },
+    // Parallelogram with corner at origin and edges u and v
+    Quad{ origin: [f64; 3], u: [f64; 3], v: [f64; 3], material: String, transform: Option<TransformDesc> },
+    // Vertices in counter-clockwise order seen from the front, with optional per-vertex normals and texture coordinates
+    Triangle{ vertices: [[f64; 3]; 3], normals: Option<[[f64; 3]; 3]>, uvs: Option<[[f64; 2]; 3]>, material: String, transform: Option<TransformDesc> },
+    // Wavefront OBJ model, path is relative to the scene file, material is used for faces without one in the MTL library
+    Obj{ path: PathBuf, material: Option<String>, transform: Option<TransformDesc> },
+    // PLY and STL meshes, material is used for all faces, PLY vertex colors replace its color
+    Ply{ path: PathBuf, material: Option<String>, transform: Option<TransformDesc> },
+    Stl{ path: PathBuf, material: Option<String>, transform: Option<TransformDesc> },
+    // glTF 2.0 scene (.gltf or .glb) with its own materials, cameras of the file are ignored
+    Gltf{ path: PathBuf, transform: Option<TransformDesc> },
+}
+
+impl ObjectDesc{
+    // Every object can be placed with a transform
+    fn transform(&self) -> Option<&TransformDesc>{
+        match self {
+            ObjectDesc::Sphere { transform, .. } | ObjectDesc::Plane { transform, .. } |
+            ObjectDesc::Disk { transform, .. } | ObjectDesc::Quad { transform, .. } |
+            ObjectDesc::Triangle { transform, .. } | ObjectDesc::Obj { transform, .. } |
+            ObjectDesc::Ply { transform, .. } | ObjectDesc::Stl { transform, .. } |
+            ObjectDesc::Gltf { transform, .. } => transform.as_ref(),
+        }
+    }
+}
+
+// Loads a TOML scene file, glTF files (.gltf, .glb) can be rendered directly as well.
+pub fn load(path: &Path) -> Result<SceneDescription, SceneError>{
+    let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase);
+    if matches!(extension.as_deref(), Some("gltf") | Some("glb")) {
+        return load_gltf(path)
+    }
+
+    let source = fs::read_to_string(path).map_err(|error| SceneError::Io(path.to_path_buf(), error))?;
+
+    parse_in(&source, path.parent().unwrap_or(Path::new("")))
+}
+
+// Parses scene file source, model paths are taken relative to the working directory.
+pub fn parse(source: &str) -> Result<SceneDescription, SceneError>{
+    parse_in(source, Path::new(""))
+}
+
+// Parses scene file source, model paths are taken relative to `directory`.
+pub fn parse_in(source: &str, directory: &Path) -> Result<SceneDescription, SceneError>{
+    let file: SceneFile = toml::from_str(source).map_err(SceneError::Parse)?;
+
+    let mut materials = BTreeMap::new();
+    for (name, material) in &file.materials {
+        materials.insert(name.as_str(), build_material(source, material)?);
+    }
+
+    let mut world = HittableList::new();
+    // Models placed with a transform, each file is loaded once and shared by all of its instances
+    let mut models: HashMap<(PathBuf, Option<String>), Arc<dyn Hittable>> = HashMap::new();
+    // Glowing quads and spheres, shadow rays are aimed at them
+    let mut lights = Vec::new();
+
+    for object in &file.objects {
+        let transform = match object.get_ref().transform() {
+            Some(transform) => Some(build_transform(source, object, transform)?),
+            None => None,
+        };
+
+        let material_name = match object.get_ref() {
+            ObjectDesc::Sphere { material, .. } | ObjectDesc::Plane { material, .. } |
+            ObjectDesc::Disk { material, .. } | ObjectDesc::Quad { material, .. } |
+            ObjectDesc::Triangle { material, .. } => Some(material),
+            ObjectDesc::Obj { material, .. } | ObjectDesc::Ply { material, .. } |
+            ObjectDesc::Stl { material, .. } => material.as_ref(),
+            ObjectDesc::Gltf { .. } => None,
+        };
+        let material = match material_name {
+            Some(name) => *materials.get(name.as_str()).ok_or_else(|| {
+                invalid(source, key_offset(source, object, "material"), format!("unknown material `{}`", name))
+            })?,
+            None => Material::Lambertian(Lambertian::new(Srgb::new(0.8, 0.8, 0.8))),
+        };
+
+        let model_key = |path: &PathBuf| (directory.join(path), material_name.cloned());
+
+        match object.get_ref() {
+            ObjectDesc::Sphere { center, radius, .. } => {
+                if *radius <= 0.0 {
+                    return Err(invalid(source, key_offset(source, object, "radius"), format!("sphere radius must be positive, got {}", radius)));
+                }
+
+                // Stretched spheres aren't round anymore, light sampling can't aim at them
+                if material.is_emissive() {
+                    match &transform {
+                        Some(transform) => if let Some(scale) = transform.uniform_scale() {
+                            lights.push(Light::sphere(transform.point(to_vec3(*center)), *radius * scale));
+                        }
+                        None => lights.push(Light::sphere(to_vec3(*center), *radius)),
+                    }
+                }
+
+                place(&mut world, Sphere::new(to_vec3(*center), *radius, material), transform);
+            }
+            ObjectDesc::Plane { point, normal, .. } => {
+                let normal = non_zero(source, object, "normal", *normal)?;
+
+                place(&mut world, Plane::new(to_vec3(*point), normal, material), transform);
+            }
+            ObjectDesc::Disk { center, normal, radius, .. } => {
+                let normal = non_zero(source, object, "normal", *normal)?;
+                if *radius <= 0.0 {
+                    return Err(invalid(source, key_offset(source, object, "radius"), format!("disk radius must be positive, got {}", radius)));
+                }
+
+                // Same for stretched disks
+                if material.is_emissive() {
+                    match &transform {
+                        Some(transform) => if let Some(scale) = transform.uniform_scale() {
+                            lights.push(Light::disk(transform.point(to_vec3(*center)), transform.normal(normal), *radius * scale));
+                        }
+                        None => lights.push(Light::disk(to_vec3(*center), normal, *radius)),
+                    }
+                }
+
+                place(&mut world, Disk::new(to_vec3(*center), normal, *radius, material), transform);
+            }
+            ObjectDesc::Quad { origin, u, v, .. } => {
+                let (u, v) = (to_vec3(*u), to_vec3(*v));
+                if u.cross(&v).near_zero() {
+                    return Err(invalid(source, key_offset(source, object, "v"), "quad edges u and v must not be parallel or zero".to_string()));
+                }
+
+                if material.is_emissive() {
+                    let light = match &transform {
+                        Some(transform) => Light::quad(transform.point(to_vec3(*origin)), transform.vector(u), transform.vector(v)),
+                        None => Light::quad(to_vec3(*origin), u, v),
+                    };
+                    lights.push(light);
+                }
+
+                place(&mut world, Quad::new(to_vec3(*origin), u, v, material), transform);
+            }
+            ObjectDesc::Triangle { vertices, normals, uvs, .. } => {
+                let [a, b, c] = vertices.map(to_vec3);
+                if (b - a).cross(&(c - a)).near_zero() {
+                    return Err(invalid(source, key_offset(source, object, "vertices"), "triangle vertices must not lie on one line".to_string()));
+                }
+
+                if material.is_emissive() {
+                    let corners = vertices.map(|vertex| match &transform {
+                        Some(transform) => transform.point(to_vec3(vertex)),
+                        None => to_vec3(vertex),
+                    });
+                    lights.push(Light::triangle(corners[0], corners[1], corners[2]));
+                }
+
+                let mut triangle = Triangle::new(a, b, c, material);
+
+                if let Some(normals) = normals {
+                    if normals.iter().any(|normal| to_vec3(*normal).near_zero()) {
+                        return Err(invalid(source, key_offset(source, object, "normals"), "triangle normals must not be zero vectors".to_string()));
+                    }
+                    triangle = triangle.with_normals(normals.map(to_vec3));
+                }
+                if let Some(uvs) = uvs {
+                    triangle = triangle.with_uvs(uvs.map(|uv| (uv[0], uv[1])));
+                }
+
+                place(&mut world, triangle, transform);
+            }
+            ObjectDesc::Obj { path, .. } => {
+                place_model(&mut world, &mut models, model_key(path), transform, || {
+                    obj::load(&directory.join(path), material)
+                }).map_err(|error| invalid(source, key_offset(source, object, "path"), error.to_string()))?;
+            }
+            ObjectDesc::Ply { path, .. } => {
+                place_model(&mut world, &mut models, model_key(path), transform, || {
+                    Ok(vec![ply::load(&directory.join(path), material)?])
+                }).map_err(|error| invalid(source, key_offset(source, object, "path"), error.to_string()))?;
+            }
+            ObjectDesc::Stl { path, .. } => {
+                place_model(&mut world, &mut models, model_key(path), transform, || {
+                    Ok(vec![stl::load(&directory.join(path), material)?])
+                }).map_err(|error| invalid(source, key_offset(source, object, "path"), error.to_string()))?;
+            }
+            ObjectDesc::Gltf { path, .. } => {
+                place_model(&mut world, &mut models, model_key(path), transform, || {
+                    Ok(gltf_file::load(&directory.join(path))?.meshes)
+                }).map_err(|error| invalid(source, key_offset(source, object, "path"), error.to_string()))?;
+            }
+        }
+    }
+
+    let mut scene = Scene::new(world);
+    for light in lights {
+        scene = scene.with_light(light);
+    }
+    if let Some(environment) = &file.environment {
+        scene = scene.with_environment(build_environment(source, directory, environment)?);
+    }
+
+    Ok(SceneDescription {
+        scene,
+        camera: build_camera(source, &file.camera, file.render.as_ref())?,
+    })
+}
+
+// Adds the object as it is, or as an instance when it has a transform.
+fn place<H: Hittable + 'static>(world: &mut HittableList, object: H, transform: Option<Transform>){
+    match transform {
+        Some(transform) => world.add(Instance::new(Arc::new(object), transform)),
+        None => world.add(object),
+    }
+}
+
+// Adds the meshes of a model file. Transformed models are loaded only once and shared between their instances.
+fn place_model<F>(world: &mut HittableList, models: &mut HashMap<(PathBuf, Option<String>), Arc<dyn Hittable>>, key: (PathBuf, Option<String>),
+    transform: Option<Transform>, load: F) -> Result<(), MeshError>
+where F: FnOnce() -> Result<Vec<TriangleMesh>, MeshError>{
+    let Some(transform) = transform else {
+        for mesh in load()? {
+            world.add(mesh);
+        }
+
+        return Ok(())
+    };
+
+    let model = match models.get(&key) {
+        Some(model) => model.clone(),
+        None => {
+            let model: Arc<dyn Hittable> = Arc::new(Bvh::new(load()?));
+            models.insert(key, model.clone());
+            model
+        }
+    };
+
+    world.add(Instance::new(model, transform));
+
+    Ok(())
+}
+
+fn build_transform<T>(source: &str, table: &Spanned<T>, desc: &TransformDesc) -> Result<Transform, SceneError>{
+    let scale = match desc.scale {
+        Some(ScaleDesc::Uniform(factor)) => Vec3::new(factor, factor, factor),
+        Some(ScaleDesc::Axes(factors)) => to_vec3(factors),
+        None => Vec3::new(1.0, 1.0, 1.0),
+    };
+
+    let mut transform = Transform::scale(scale).ok_or_else(|| {
+        invalid(source, key_offset(source, table, "transform"), "transform scale must not be zero".to_string())
+    })?;
+
+    if let Some([x, y, z]) = desc.rotate {
+        transform = transform
+            .then(&Transform::rotate(Vec3::new(1.0, 0.0, 0.0), x))
+            .then(&Transform::rotate(Vec3::new(0.0, 1.0, 0.0), y))
+            .then(&Transform::rotate(Vec3::new(0.0, 0.0, 1.0), z));
+    }
+    if let Some(translate) = desc.translate {
+        transform = transform.then(&Transform::translate(to_vec3(translate)));
+    }
+
+    Ok(transform)
+}
+
+// Scene of a glTF file, viewed by its first camera or from the front when it has none.
+fn load_gltf(path: &Path) -> Result<SceneDescription, SceneError>{
+    let gltf = gltf_file::load(path).map_err(SceneError::Mesh)?;
+
+    let mut world = HittableList::new();
+    for mesh in gltf.meshes {
+        world.add(mesh);
+    }
+
+    let mut camera = Camera::builder(600, 50);
+    match gltf.camera {
+        Some(gltf_camera) => camera = gltf_camera.apply(camera),
+        None if !world.is_empty() => {
+            let bounds = world.bounding_box();
+            let center = bounds.centroid();
+            let radius = Vec3::new(bounds.x.size(), bounds.y.size(), bounds.z.size()).length() / 2.0;
+            let vfov: f64 = 40.0;
+
+            camera = camera.vfov(vfov)
+                .look_at(center)
+                .look_from(center + Vec3::new(0.0, 0.0, 1.1 * radius / (vfov / 2.0).to_radians().sin()));
+        }
+        None => {}
+    }
+    camera.validate().map_err(SceneError::Camera)?;
+
+    Ok(SceneDescription { scene: Scene::new(world), camera })
+}
+
+fn build_material(source: &str, material: &Spanned<MaterialDesc>) -> Result<Material, SceneError>{
+    match material.get_ref() {
+        MaterialDesc::Lambertian { albedo } => Ok(Material::Lambertian(Lambertian::new(to_color(*albedo)))),
+        MaterialDesc::Metal { albedo, fuzz } => Ok(Material::Metallic(Metal::new(to_color(*albedo), *fuzz))),
+        MaterialDesc::Pbr { base_color, metallic, roughness, emission } => {
+            let pbr = Pbr::new(to_color(*base_color), *metallic, *roughness).with_emission(to_color(emission.unwrap_or([0.0; 3])));
+
+            Ok(Material::Pbr(pbr))
+        }
+        MaterialDesc::Light { emit, two_sided } => {
+            if emit.iter().any(|channel| *channel < 0.0) {
+                return Err(invalid(source, key_offset(source, material, "emit"), "light emit must not be negative".to_string()));
+            }
+
+            Ok(Material::DiffuseLight(DiffuseLight::new(to_color(*emit)).with_two_sided(*two_sided)))
+        }
+        MaterialDesc::Glass { refraction_index } => {
+            if *refraction_index <= 0.0 {
+                return Err(invalid(source, key_offset(source, material, "refraction_index"), format!("refraction_index must be positive, got {}", refraction_index)));
+            }
+
+            Ok(Material::Glass(Glass::new(*refraction_index)))
+        }
+    }
+}
+
+fn build_environment(source: &str, directory: &Path, environment: &Spanned<EnvironmentDesc>) -> Result<Environment, SceneError>{
+    match environment.get_ref() {
+        EnvironmentDesc::Constant { color } => Ok(Environment::Constant(to_color(*color))),
+        EnvironmentDesc::Gradient { bottom, top } => Ok(Environment::Gradient { bottom: to_color(*bottom), top: to_color(*top) }),
+        EnvironmentDesc::Map { path, rotation, intensity } => {
+            if *intensity < 0.0 {
+                return Err(invalid(source, key_offset(source, environment, "intensity"), format!("environment intensity must not be negative, got {}", intensity)));
+            }
+
+            let map = EnvironmentMap::load(&directory.join(path))
+                .map_err(|error| invalid(source, key_offset(source, environment, "path"), error.to_string()))?;
+
+            Ok(Environment::Map(map.with_rotation(*rotation).with_intensity(*intensity)))
+        }
+    }
+}
+
+fn build_camera(source: &str, desc: &Spanned<CameraDesc>, render_desc: Option<&Spanned<RenderDesc>>) -> Result<CameraBuilder, SceneError>{
+    let camera = desc.get_ref();
+    let default_render = RenderDesc::default();
+    let render = render_desc.map_or(&default_render, |render| render.get_ref());
+    let mut builder = Camera::builder(camera.width, render.max_depth)
+        .samples_per_pixel(render.samples_per_pixel)
+        .seed(render.seed)
+        .sampler(match render.sampler {
+            SamplerDesc::Independent => SamplerType::Independent,
+            SamplerDesc::Stratified => SamplerType::Stratified,
+            SamplerDesc::Halton => SamplerType::Halton,
+            SamplerDesc::Sobol => SamplerType::Sobol,
+        })
+        .filter(match render.filter {
+            FilterDesc::Box => FilterType::Box,
+            FilterDesc::Tent => FilterType::Tent,
+            FilterDesc::Gaussian => FilterType::Gaussian,
+            FilterDesc::Mitchell => FilterType::Mitchell,
+            FilterDesc::Lanczos => FilterType::Lanczos,
+        });
+
+    if let Some(filter_radius) = render.filter_radius { builder = builder.filter_radius(filter_radius) }
+    if let Some(aspect_ratio) = camera.aspect_ratio { builder = builder.aspect_ratio(aspect_ratio) }
+    if let Some(height) = camera.height { builder = builder.image_height(height) }
+    if let Some(look_from) = camera.look_from { builder = builder.look_from(to_vec3(look_from)) }
+    if let Some(look_at) = camera.look_at { builder = builder.look_at(to_vec3(look_at)) }
+    if let Some(vup) = camera.vup { builder = builder.vup(to_vec3(vup)) }
+    if let Some(vfov) = camera.vfov { builder = builder.vfov(vfov) }
+    if let Some(defocus_angle) = camera.defocus_angle { builder = builder.defocus_angle(defocus_angle) }
+    if let Some(focus_dist) = camera.focus_dist { builder = builder.focus_dist(focus_dist) }
+
+    builder.validate().map_err(|error| {
+        let key = match error {
+            CameraError::UpParallelToView if camera.vup.is_some() => "vup",
+            CameraError::ZeroViewDirection | CameraError::UpParallelToView => "look_at",
+            CameraError::Vfov(_) => "vfov",
+            CameraError::FocusDist(_) => "focus_dist",
+            CameraError::AspectRatio(_) => "aspect_ratio",
+            CameraError::ImageWidth(_) => "width",
+            CameraError::ImageHeight(_) if camera.height.is_some() => "height",
+            CameraError::ImageHeight(_) => "aspect_ratio",
+            CameraError::SamplesPerPixel => "samples_per_pixel",
+            CameraError::FilterRadius(_) => "filter_radius",
+        };
+        // Render settings are checked by the camera too, they are found in their own table
+        let offset = match (error, render_desc) {
+            (CameraError::SamplesPerPixel | CameraError::FilterRadius(_), Some(render_desc)) => key_offset(source, render_desc, key),
+            _ => key_offset(source, desc, key),
+        };
+
+        invalid(source, offset, error.to_string())
+    })?;
+
+    Ok(builder)
+}
+
+// Offset of the line defining `key` inside of a spanned table, falls back to the start of the table.
+fn key_offset<T>(source: &str, table: &Spanned<T>, key: &str) -> usize{
+    let span = table.span();
+    let mut offset = span.start;
+
+    for line in source[span.clone()].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(key) && trimmed[key.len()..].trim_start().starts_with('=') {
+            return offset + (line.len() - trimmed.len());
+        }
+        offset += line.len();
+    }
+
+    span.start
+}
+
+// Builds an error pointing at the byte offset inside of the scene file.
+fn invalid(source: &str, offset: usize, message: String) -> SceneError{
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    let source_line = source[line_start..].lines().next().unwrap_or("").to_string();
+
+    SceneError::Invalid { line, column, source_line, message }
+}
+
+// Direction given by `key`, which has to have some length.
+fn non_zero<T>(source: &str, table: &Spanned<T>, key: &str, v: [f64; 3]) -> Result<Vec3, SceneError>{
+    let vector = to_vec3(v);
+    if vector.near_zero() {
+        return Err(invalid(source, key_offset(source, table, key), format!("{} must not be a zero vector", key)));
+    }
+
+    Ok(vector)
+}
+
+fn to_vec3(v: [f64; 3]) -> Vec3{
+    Vec3::new(v[0], v[1], v[2])
+}
+
+fn to_color(c: [f32; 3]) -> Srgb{
+    Srgb::new(c[0], c[1], c[2])
+}
@@ -1,8 +1,4 @@
-use crate::{ray, hit_record, vector, material::{Material, Lambertian}};
-use palette::Srgb;
-pub trait Hittable{
-    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<hit_record::HitRecord>;
-} 
+use crate::{ray, hit_record, vector, aabb::Aabb, material::Material, hittable::Hittable};
 
 pub struct Sphere{
     center: vector::Vec3,
@@ -18,6 +14,14 @@ impl Sphere{
             material,
         }
     }
+
+    // Longitude and latitude of a point on the unit sphere mapped to [0, 1], v goes from the bottom (-Y) to the top.
+    fn uv(p: &vector::Vec3) -> (f64, f64) {
+        let theta = (-p.y()).clamp(-1.0, 1.0).acos();
+        let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
+
+        (phi / (2.0 * std::f64::consts::PI), theta / std::f64::consts::PI)
+    }
 }
 
 impl Hittable for Sphere{
@@ -31,24 +35,23 @@ impl Hittable for Sphere{
         if discriminant > 0.0 {
             let root = discriminant.sqrt();
 
-            let temp = (-half_b - root) / a;
-
-            if temp < t_max && temp > t_min {
+            // Far root is the way out for rays starting inside, like the ones refracted into glass or seen inside a glowing shell.
+            for temp in [(-half_b - root) / a, (-half_b + root) / a] {
+                if temp < t_max && temp > t_min {
 
-                let p = ray.at(temp);
-                let normal = (p - self.center) / self.radius;
-                let front_face = ray.direction().dot(&normal) < 0.0;
+                    let outward_normal = (ray.at(temp) - self.center) / self.radius;
 
-                return Some(hit_record::HitRecord {
-                    t: temp,
-                    point: p,
-                    normal: if front_face {normal } else { -normal},
-                    front_face,
-                    material: self.material,
-                })
+                    return Some(hit_record::HitRecord::from_outward_normal(ray, temp, outward_normal, Sphere::uv(&outward_normal), self.material))
+                }
             }
         }
 
         None
     }
-}
\ No newline at end of file
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = vector::Vec3::new(self.radius, self.radius, self.radius);
+
+        Aabb::from_points(self.center - radius, self.center + radius)
+    }
+}
@@ -0,0 +1,31 @@
+use std::{fs::File, io::BufReader, path::Path};
+use crate::{vector::Vec3, triangle::Triangle, mesh::{TriangleMesh, MeshError}, material::Material};
+
+// Loads an ASCII or binary STL file into one mesh named after the file.
+// Facet normals are left out, STL surfaces are flat shaded by the triangle winding anyway.
+pub fn load(path: &Path, material: Material) -> Result<TriangleMesh, MeshError>{
+    let file = File::open(path).map_err(|error| MeshError::Io(path.to_path_buf(), error))?;
+    let mut reader = BufReader::new(file);
+
+    let facets = stl_io::create_stl_reader(&mut reader).map_err(|error| MeshError::Format(path.to_path_buf(), error.to_string()))?;
+
+    let mut triangles = Vec::new();
+    for facet in facets {
+        let facet = facet.map_err(|error| MeshError::Format(path.to_path_buf(), error.to_string()))?;
+        let [a, b, c] = facet.vertices.map(|vertex| Vec3::new(vertex.0[0] as f64, vertex.0[1] as f64, vertex.0[2] as f64));
+
+        // CAD exports often hold slivers with coinciding corners, they can never be hit.
+        if (b - a).cross(&(c - a)).length_squared() == 0.0 {
+            continue
+        }
+
+        triangles.push(Triangle::new(a, b, c, material));
+    }
+
+    Ok(TriangleMesh::new(&mesh_name(path), triangles))
+}
+
+// Name of a single mesh file, the file name without extension.
+pub(crate) fn mesh_name(path: &Path) -> String{
+    path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())
+}
@@ -1,7 +1,5 @@
-#[cfg(test)]
 use assert_approx_eq::assert_approx_eq;
 
-#[warn(unused_imports)]
 use super::*;
 
 #[test]
@@ -161,3 +159,1802 @@ fn test_ray_at(){
 //     let hit = sphere.hit(&ray, 0.0, f64::INFINITY);
 //     assert_approx_eq!(hit.unwrap().t, 4.0);
 // }
+
+#[test]
+fn test_camera_default_looks_down_negative_z(){
+    let camera = camera::Camera::new(400, 10).unwrap();
+
+    assert_approx_eq!(camera.w.z(), 1.0);
+    assert_approx_eq!(camera.u.x(), 1.0);
+    assert_approx_eq!(camera.v.y(), 1.0);
+    assert_approx_eq!(camera.center.length(), 0.0);
+}
+
+#[test]
+fn test_camera_look_from_look_at(){
+    let camera = camera::Camera::builder(400, 10)
+        .look_from(vector::Vec3::new(-2.0, 2.0, 1.0))
+        .look_at(vector::Vec3::new(0.0, 0.0, -1.0))
+        .vup(vector::Vec3::new(0.0, 1.0, 0.0))
+        .vfov(20.0)
+        .build().unwrap();
+
+    // w points from look_at back towards the camera
+    let expected_w = vector::Vec3::new(-2.0, 2.0, 2.0).unit_vector();
+    assert_approx_eq!(camera.w.x(), expected_w.x());
+    assert_approx_eq!(camera.w.y(), expected_w.y());
+    assert_approx_eq!(camera.w.z(), expected_w.z());
+
+    // Basis is orthonormal
+    assert_approx_eq!(camera.u.dot(&camera.v), 0.0);
+    assert_approx_eq!(camera.u.dot(&camera.w), 0.0);
+    assert_approx_eq!(camera.v.dot(&camera.w), 0.0);
+
+    assert_approx_eq!(camera.center.x(), -2.0);
+}
+
+#[test]
+fn test_camera_rejects_degenerate_view(){
+    let same_point = camera::Camera::builder(400, 10).look_from(vector::Vec3::new(0.0, 0.0, -1.0)).build();
+    assert_eq!(same_point.err(), Some(camera::CameraError::ZeroViewDirection));
+
+    // Straight down with the default vup
+    let top_down = camera::Camera::builder(400, 10).look_from(vector::Vec3::new(0.0, 5.0, 0.0)).look_at(vector::Vec3::new(0.0, 0.0, 0.0));
+    assert_eq!(top_down.build().err(), Some(camera::CameraError::UpParallelToView));
+    assert!(top_down.vup(vector::Vec3::new(0.0, 0.0, -1.0)).build().is_ok());
+
+    let source = "[camera]\nwidth = 16\nlook_from = [0.0, 5.0, 0.0]\nlook_at = [0.0, 0.0, 0.0]\nvup = [0.0, 2.0, 0.0]\n";
+    let message = scene_file::parse(source).err().unwrap().to_string();
+    assert!(message.contains("line 5") && message.contains("vup"), "{}", message);
+
+    // Field of view has to open, but less than half way around
+    for vfov in [0.0, -20.0, 180.0, f64::NAN] {
+        assert!(matches!(camera::Camera::builder(400, 10).vfov(vfov).build(), Err(camera::CameraError::Vfov(_))));
+    }
+    assert!(camera::Camera::builder(400, 10).vfov(179.0).build().is_ok());
+
+    for focus_dist in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(camera::Camera::builder(400, 10).focus_dist(focus_dist).build(), Err(camera::CameraError::FocusDist(_))));
+    }
+
+    let source = "[camera]\nwidth = 16\nvfov = 40.0\nfocus_dist = 0.0\n";
+    let message = scene_file::parse(source).err().unwrap().to_string();
+    assert!(message.contains("line 4") && message.contains("focus_dist"), "{}", message);
+}
+
+#[test]
+fn test_camera_defocus_disk_sample(){
+    let camera = camera::Camera::builder(400, 10)
+        .defocus_angle(10.0)
+        .focus_dist(3.4)
+        .build().unwrap();
+
+    let radius = 3.4 * (5.0_f64).to_radians().tan();
+    assert_approx_eq!(camera.defocus_disk_u.length(), radius);
+
+    for i in 0..100 {
+        let origin = camera.defocus_disk_sample((i as f64 / 100.0, (i * 37 % 100) as f64 / 100.0));
+
+        assert!((origin - camera.center).length() <= radius);
+        assert_approx_eq!(origin.z(), camera.center.z());
+    }
+}
+
+#[test]
+fn test_camera_aspect_ratio(){
+    let camera = camera::Camera::new(1920, 10).unwrap();
+
+    assert_eq!(camera.image_height, 1080);
+    // Viewport keeps the image proportions
+    let ratio = camera.pixel_vec_u.length() / camera.pixel_vec_v.length();
+    assert_approx_eq!(ratio, 1.0);
+
+    let square = camera::Camera::builder(256, 10).aspect_ratio(1.0).build().unwrap();
+    assert_eq!(square.image_height, 256);
+
+    let cinematic = camera::Camera::builder(956, 10).aspect_ratio(2.39).build().unwrap();
+    assert_eq!(cinematic.image_height, 400);
+
+    let portrait = camera::Camera::builder(300, 10).image_height(500).build().unwrap();
+    assert_eq!(portrait.image_height, 500);
+    assert_approx_eq!(portrait.pixel_vec_u.length(), portrait.pixel_vec_v.length());
+}
+
+#[test]
+fn test_camera_rejects_bad_image_size(){
+    // Too wide for a single row of pixels
+    assert_eq!(camera::Camera::builder(10, 10).aspect_ratio(100.0).build().err(), Some(camera::CameraError::ImageHeight(0)));
+
+    for aspect_ratio in [0.0, -1.5, f64::NAN, f64::INFINITY] {
+        assert!(matches!(camera::Camera::builder(10, 10).aspect_ratio(aspect_ratio).build(), Err(camera::CameraError::AspectRatio(_))));
+    }
+    assert_eq!(camera::Camera::builder(640, 10).aspect_ratio(1e-9).build().err(), Some(camera::CameraError::ImageHeight(i32::MAX)));
+    assert_eq!(camera::Camera::builder(0, 10).build().err(), Some(camera::CameraError::ImageWidth(0)));
+    assert_eq!(camera::Camera::builder(10, 10).image_height(-3).build().err(), Some(camera::CameraError::ImageHeight(-3)));
+
+    // Explicit height makes the aspect ratio unused
+    assert!(camera::Camera::builder(10, 10).aspect_ratio(0.0).image_height(10).build().is_ok());
+
+    let source = "[camera]\nwidth = 640\naspect_ratio = 0.0\n";
+    let message = scene_file::parse(source).err().unwrap().to_string();
+    assert!(message.contains("line 3") && message.contains("aspect_ratio"), "{}", message);
+}
+
+#[test]
+fn test_framebuffer_set_get(){
+    let mut framebuffer = framebuffer::Framebuffer::new(4, 3);
+
+    framebuffer.set(3, 2, palette::Srgb::new(0.25, 0.5, 2.0));
+
+    assert_eq!(framebuffer.pixels().len(), 12);
+    assert_approx_eq!(framebuffer.get(3, 2).blue, 2.0);
+    assert_approx_eq!(framebuffer.get(0, 0).red, 0.0);
+}
+
+#[test]
+fn test_output_format_from_path(){
+    use std::path::Path;
+
+    assert_eq!(output::OutputFormat::from_path(Path::new("a.png")), Some(output::OutputFormat::Png));
+    assert_eq!(output::OutputFormat::from_path(Path::new("a.JPG")), Some(output::OutputFormat::Jpeg(90)));
+    assert_eq!(output::OutputFormat::from_path(Path::new("a.ppm")), Some(output::OutputFormat::Ppm));
+    assert_eq!(output::OutputFormat::from_path(Path::new("a.exr")), Some(output::OutputFormat::Exr));
+    assert_eq!(output::OutputFormat::from_path(Path::new("a.pnn")), None);
+}
+
+#[test]
+fn test_output_ppm_header(){
+    let mut framebuffer = framebuffer::Framebuffer::new(3, 2);
+    framebuffer.set(0, 0, palette::Srgb::new(1.5, 0.5, 0.0));
+
+    let mut bytes = Vec::new();
+    output::write(&framebuffer, output::OutputFormat::Ppm, &tonemap::OutputTransform::default(), &mut bytes).unwrap();
+
+    let header = b"P6\n3 2 255\n";
+    assert_eq!(&bytes[..header.len()], header);
+    // Values are clamped and sRGB encoded before quantization
+    assert_eq!(&bytes[header.len()..header.len() + 3], &[255, 188, 0]);
+    assert_eq!(bytes.len(), header.len() + 3 * 3 * 2);
+}
+
+#[test]
+fn test_output_exr_round_trip(){
+    let mut framebuffer = framebuffer::Framebuffer::new(2, 2);
+    framebuffer.set(1, 1, palette::Srgb::new(4.0, 0.5, 0.125));
+
+    let mut bytes = Vec::new();
+    output::write(&framebuffer, output::OutputFormat::Exr, &tonemap::OutputTransform::default(), &mut bytes).unwrap();
+
+    let decoded = image::load_from_memory(&bytes).unwrap().into_rgb32f();
+    assert_approx_eq!(decoded.get_pixel(1, 1)[0], 4.0);
+    assert_approx_eq!(decoded.get_pixel(1, 1)[2], 0.125);
+}
+
+#[test]
+fn test_render_to_buffer_sky(){
+    // Camera looking straight up at an empty scene sees only the top of the sky gradient.
+    let camera = camera::Camera::builder(8, 5)
+        .aspect_ratio(1.0)
+        .samples_per_pixel(4)
+        .look_at(vector::Vec3::new(0.0, 1.0, 0.0))
+        .vup(vector::Vec3::new(0.0, 0.0, -1.0))
+        .vfov(1.0)
+        .build().unwrap();
+    let scene = scene::Scene::new(hittable::HittableList::new());
+
+    let framebuffer = render::Render::render_to_buffer(&scene, &camera);
+
+    assert_eq!(framebuffer.width(), 8);
+    assert_eq!(framebuffer.height(), 8);
+    for pixel in framebuffer.pixels() {
+        assert_approx_eq!(pixel.red, 0.5, 1e-3);
+        assert_approx_eq!(pixel.green, 0.7, 1e-3);
+        assert_approx_eq!(pixel.blue, 1.0, 1e-3);
+    }
+}
+
+#[test]
+fn test_world_is_thread_safe(){
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<sphere::Sphere>();
+    assert_send_sync::<material::Material>();
+    assert_send_sync::<scene::Scene>();
+    assert_send_sync::<camera::Camera>();
+}
+
+#[test]
+fn test_scene_file_demo(){
+    let description = scene_file::load(std::path::Path::new("scenes/demo.toml")).unwrap();
+    let camera = description.camera.build().unwrap();
+
+    assert_eq!(description.scene.world.len(), 5);
+    assert_eq!(camera.image_width, 600);
+    assert_eq!(camera.image_height, 337);
+    assert_eq!(camera.samples_per_pixel, 32);
+    assert_eq!(camera.max_depth, 50);
+}
+
+#[test]
+fn test_scene_file_unknown_material(){
+    let source = r#"
+[camera]
+width = 100
+
+[materials.red]
+type = "lambertian"
+albedo = [1.0, 0.0, 0.0]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = "blue"
+"#;
+
+    let error = scene_file::parse(source).err().unwrap();
+    let message = error.to_string();
+
+    match error {
+        scene_file::SceneError::Invalid { line, .. } => assert_eq!(line, 13),
+        _ => panic!("unexpected error: {}", message),
+    }
+    assert!(message.contains("unknown material `blue`"));
+}
+
+#[test]
+fn test_scene_file_syntax_error_has_line(){
+    let source = "[camera]\nwidth = 100\n\n[[objects]]\ntype = \"cube\"\n";
+
+    let message = scene_file::parse(source).err().unwrap().to_string();
+
+    assert!(message.contains("line 5"), "{}", message);
+}
+
+#[test]
+fn test_ray_color_depth_limit(){
+    use palette::Srgb;
+
+    // Two perfect mirrors facing each other, the ray would bounce between them forever.
+    let mirror = material::Material::Metallic(material::Metal::new(Srgb::new(1.0, 1.0, 1.0), 0.0));
+    let mut world = hittable::HittableList::new();
+    world.add(sphere::Sphere::new(vector::Vec3::new(0.0, 0.0, -2.0), 1.0, mirror));
+    world.add(sphere::Sphere::new(vector::Vec3::new(0.0, 0.0, 2.0), 1.0, mirror));
+    let world = scene::Scene::new(world);
+    let ray = ray::Ray::new(vector::Vec3::new(0.0, 0.0, 0.0), vector::Vec3::new(0.0, 0.0, -1.0));
+    let intensity = interval::Interval::new(0.001, f64::MAX);
+    let mut rng = sampler::IndependentSampler::new(0);
+
+    for depth in [0, 1, 50, 1000] {
+        let color = ray::Ray::ray_color(&ray, intensity, &world, depth, &mut rng);
+
+        assert_approx_eq!(color.red, 0.0);
+        assert_approx_eq!(color.green, 0.0);
+        assert_approx_eq!(color.blue, 0.0);
+    }
+
+    // Without any bounces left even the sky is not visible.
+    let sky = ray::Ray::new(vector::Vec3::new(0.0, 0.0, 0.0), vector::Vec3::new(0.0, 1.0, 0.0));
+    assert_approx_eq!(ray::Ray::ray_color(&sky, intensity, &world, 0, &mut rng).blue, 0.0);
+    assert_approx_eq!(ray::Ray::ray_color(&sky, intensity, &world, 1, &mut rng).blue, 1.0);
+}
+
+#[test]
+fn test_output_transform_srgb(){
+    let transform = tonemap::OutputTransform::default();
+
+    // sRGB transfer function: linear 0.5 is encoded as ~0.735, black and white stay in place
+    assert_approx_eq!(transform.apply(palette::Srgb::new(0.5, 0.0, 1.0)).red, 0.7354, 1e-3);
+    assert_approx_eq!(transform.apply(palette::Srgb::new(0.5, 0.0, 1.0)).green, 0.0);
+    assert_approx_eq!(transform.apply(palette::Srgb::new(0.5, 0.0, 1.0)).blue, 1.0);
+    assert_approx_eq!(transform.apply(palette::Srgb::new(8.0, -1.0, f32::NAN)).red, 1.0);
+    assert_approx_eq!(transform.apply(palette::Srgb::new(8.0, -1.0, f32::NAN)).green, 0.0);
+    assert_approx_eq!(transform.apply(palette::Srgb::new(8.0, -1.0, f32::NAN)).blue, 0.0);
+
+    // One stop of exposure doubles linear value
+    let brighter = tonemap::OutputTransform::new(1.0, tonemap::ToneMapper::Clamp);
+    assert_approx_eq!(brighter.apply(palette::Srgb::new(0.25, 0.25, 0.25)).red, 0.7354, 1e-3);
+}
+
+#[test]
+fn test_tone_mappers(){
+    use tonemap::ToneMapper;
+
+    assert_approx_eq!(ToneMapper::Reinhard.map(1.0), 0.5);
+    assert_approx_eq!(ToneMapper::Reinhard.map(0.0), 0.0);
+    assert_approx_eq!(ToneMapper::AcesFilmic.map(0.0), 0.0);
+    // Both curves compress highlights and keep the order of values
+    for x in [0.5_f32, 1.0, 4.0, 100.0] {
+        assert!(ToneMapper::Reinhard.map(x) < 1.0);
+        assert!(ToneMapper::AcesFilmic.map(x) < 1.04);
+        assert!(ToneMapper::AcesFilmic.map(x) < ToneMapper::AcesFilmic.map(x * 2.0));
+    }
+}
+
+#[test]
+fn test_render_is_deterministic(){
+    let scene = scene::Scene::demo();
+    let render = |seed: u64, threads: usize| {
+        let camera = camera::Camera::builder(32, 10).samples_per_pixel(4).seed(seed).defocus_angle(2.0).build().unwrap();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+
+        pool.install(|| render::Render::render_to_buffer(&scene, &camera))
+    };
+
+    let single = render(7, 1);
+    let multi = render(7, 3);
+    let other_seed = render(8, 3);
+
+    // Same seed gives bit-identical pixels regardless of the thread count.
+    for (a, b) in single.pixels().iter().zip(multi.pixels()) {
+        assert_eq!(a.red.to_bits(), b.red.to_bits());
+        assert_eq!(a.green.to_bits(), b.green.to_bits());
+        assert_eq!(a.blue.to_bits(), b.blue.to_bits());
+    }
+    assert!(single.pixels().iter().zip(other_seed.pixels()).any(|(a, b)| a.red != b.red));
+}
+
+#[test]
+fn test_sample_unit_vec3_and_disk(){
+    for i in 0..10 {
+        for j in 0..10 {
+            let u = (i as f64 / 10.0, j as f64 / 10.0);
+
+            assert_approx_eq!(vector::Vec3::sample_unit_vec3(u).length(), 1.0);
+            assert!(vector::Vec3::sample_unit_disk(u).length() <= 1.0);
+        }
+    }
+    assert_approx_eq!(vector::Vec3::sample_unit_disk((1.0, 0.5)).x(), 1.0);
+    assert_approx_eq!(vector::Vec3::sample_unit_vec3((0.0, 0.3)).z(), 1.0);
+}
+
+#[test]
+fn test_radical_inverse(){
+    assert_approx_eq!(sampler::radical_inverse(2, 1), 0.5);
+    assert_approx_eq!(sampler::radical_inverse(2, 3), 0.75);
+    assert_approx_eq!(sampler::radical_inverse(3, 1), 1.0 / 3.0);
+    assert_approx_eq!(sampler::radical_inverse(3, 5), 7.0 / 9.0);
+}
+
+// Counts 2D samples of one pixel falling into each cell of a `columns` x `rows` grid.
+#[cfg(test)]
+fn sample_grid(sampler: &mut dyn sampler::Sampler, samples: u32, columns: usize, rows: usize) -> Vec<u32> {
+    let mut cells = vec![0; columns * rows];
+
+    for index in 0..samples {
+        sampler.start_sample(3, 5, index);
+        let (u, v) = sampler.get_2d();
+
+        assert!((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v));
+        cells[(v * rows as f64) as usize * columns + (u * columns as f64) as usize] += 1;
+    }
+
+    cells
+}
+
+#[test]
+fn test_stratified_sampler_covers_strata(){
+    let mut stratified = sampler::StratifiedSampler::new(1, 32);
+
+    // 32 samples are laid out on 4x8 grid, one in each cell
+    assert!(sample_grid(&mut stratified, 32, 4, 8).iter().all(|count| *count == 1));
+}
+
+#[test]
+fn test_sobol_sampler_is_stratified(){
+    use sampler::Sampler;
+
+    let mut sobol = sampler::SobolSampler::new(1, 16);
+
+    // Scrambled Sobol points keep (0,2)-net property, every elementary interval holds one point
+    for (columns, rows) in [(16, 1), (8, 2), (4, 4), (2, 8), (1, 16)] {
+        assert!(sample_grid(&mut sobol, 16, columns, rows).iter().all(|count| *count == 1));
+    }
+
+    // Later dimensions are stratified as well
+    let mut cells = [0; 16];
+    for index in 0..16 {
+        sobol.start_sample(3, 5, index);
+        sobol.get_2d();
+        sobol.get_2d();
+        cells[(sobol.get_1d() * 16.0) as usize] += 1;
+    }
+    assert!(cells.iter().all(|count| *count == 1));
+}
+
+#[test]
+fn test_halton_sampler_is_stratified(){
+    let mut halton = sampler::HaltonSampler::new(1);
+
+    // 6 consecutive samples of bases 2 and 3 land in distinct cells of 2x3 grid
+    assert!(sample_grid(&mut halton, 6, 2, 3).iter().all(|count| *count == 1));
+}
+
+#[test]
+fn test_samplers_are_deterministic(){
+    for kind in [sampler::SamplerType::Independent, sampler::SamplerType::Stratified, sampler::SamplerType::Halton, sampler::SamplerType::Sobol] {
+        let mut a = kind.create(9, 16);
+        let mut b = kind.create(9, 16);
+
+        b.start_sample(1, 1, 4);
+        b.get_2d();
+        a.start_sample(2, 7, 3);
+        b.start_sample(2, 7, 3);
+
+        for _ in 0..8 {
+            assert_eq!(a.get_2d(), b.get_2d());
+            assert_eq!(a.get_1d(), b.get_1d());
+        }
+    }
+}
+
+#[test]
+fn test_filter_weights(){
+    use filter::{Filter, FilterType};
+
+    for kind in [FilterType::Box, FilterType::Tent, FilterType::Gaussian, FilterType::Mitchell, FilterType::Lanczos] {
+        let filter = Filter::new(kind, kind.default_radius()).unwrap();
+
+        // Filters peak at the pixel center, are symmetric and vanish outside of the radius
+        assert!(filter.evaluate(0.0, 0.0) > 0.0);
+        assert!(filter.evaluate(0.0, 0.0) >= filter.evaluate(0.3, 0.2));
+        assert_approx_eq!(filter.evaluate(0.4, -0.3), filter.evaluate(-0.4, 0.3));
+        assert_eq!(filter.evaluate(filter.radius + 0.01, 0.0), 0.0);
+    }
+
+    // Lanczos crosses zero at whole pixel offsets, Mitchell has negative lobes
+    assert_approx_eq!(Filter::new(FilterType::Lanczos, 3.0).unwrap().evaluate(1.0, 0.0), 0.0);
+    assert!(Filter::new(FilterType::Mitchell, 2.0).unwrap().evaluate(1.5, 0.0) < 0.0);
+    assert_approx_eq!(Filter::new(FilterType::Tent, 1.0).unwrap().evaluate(0.5, 0.0), 0.5);
+
+    // Radius can't get below half a pixel
+    for radius in [0.1, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(Filter::new(FilterType::Box, radius).is_none());
+    }
+    assert!(Filter::new(FilterType::Box, 0.5).is_some());
+
+    // Cameras report bad render settings instead of changing them
+    assert_eq!(camera::Camera::builder(64, 10).samples_per_pixel(0).build().err(), Some(camera::CameraError::SamplesPerPixel));
+    assert_eq!(camera::Camera::builder(64, 10).filter_radius(0.1).build().err(), Some(camera::CameraError::FilterRadius(0.1)));
+
+    let source = "[camera]\nwidth = 16\n\n[render]\nfilter = \"tent\"\nfilter_radius = 0.25\n";
+    let message = scene_file::parse(source).err().unwrap().to_string();
+    assert!(message.contains("line 6") && message.contains("filter radius"), "{}", message);
+    let source = "[camera]\nwidth = 16\n\n[render]\nsamples_per_pixel = 0\n";
+    let message = scene_file::parse(source).err().unwrap().to_string();
+    assert!(message.contains("line 5"), "{}", message);
+}
+
+#[test]
+fn test_film_box_filter_averages_pixel(){
+    let mut film = film::Film::new(3, 3, filter::Filter::default());
+    let mut tile = film.tile(0, 3);
+
+    tile.add_sample(1.2, 1.7, palette::Srgb::new(1.0, 0.0, 0.0));
+    tile.add_sample(1.9, 1.0, palette::Srgb::new(0.0, 1.0, 0.0));
+    film.merge_tile(&tile);
+
+    let framebuffer = film.to_framebuffer();
+
+    assert_approx_eq!(framebuffer.get(1, 1).red, 0.5);
+    assert_approx_eq!(framebuffer.get(1, 1).green, 0.5);
+    // Box filter of pixel size doesn't leak into neighbours
+    assert_eq!(framebuffer.get(0, 1).red, 0.0);
+    assert_eq!(framebuffer.get(2, 1).green, 0.0);
+}
+
+#[test]
+fn test_film_splats_across_tiles(){
+    let filter = filter::Filter::new(filter::FilterType::Tent, 1.5).unwrap();
+    let mut film = film::Film::new(4, 6, filter);
+
+    // Sample of row 2 reaches row 3, which belongs to the next tile
+    let mut top = film.tile(0, 3);
+    let bottom = film.tile(3, 6);
+    top.add_sample(1.5, 2.9, palette::Srgb::new(1.0, 1.0, 1.0));
+    film.merge_tile(&top);
+    film.merge_tile(&bottom);
+
+    let framebuffer = film.to_framebuffer();
+
+    assert_approx_eq!(framebuffer.get(1, 2).red, 1.0);
+    assert_approx_eq!(framebuffer.get(1, 3).red, 1.0);
+    assert_approx_eq!(framebuffer.get(2, 3).red, 1.0);
+    assert_eq!(framebuffer.get(1, 5).red, 0.0);
+}
+
+#[test]
+fn test_film_negative_weight_falls_back_to_box(){
+    let filter = filter::Filter::new(filter::FilterType::Mitchell, 2.0).unwrap();
+    let mut film = film::Film::new(4, 3, filter);
+    let mut tile = film.tile(0, 3);
+
+    // Corner sample of pixel (1, 1) is outweighed by the negative lobe of many samples two pixels away
+    tile.add_sample(1.99, 1.99, palette::Srgb::new(0.25, 0.25, 0.25));
+    for _ in 0..50 {
+        tile.add_sample(3.3, 1.5, palette::Srgb::new(1.0, 1.0, 1.0));
+    }
+    film.merge_tile(&tile);
+    assert!(filter.evaluate(-0.49, -0.49) + 50.0 * filter.evaluate(-1.8, 0.0) <= 0.0);
+
+    let framebuffer = film.to_framebuffer();
+    assert_approx_eq!(framebuffer.get(1, 1).red, 0.25);
+    assert_approx_eq!(framebuffer.get(3, 1).red, 1.0, 1e-2);
+}
+
+#[test]
+fn test_filtered_render_is_deterministic(){
+    let scene = scene::Scene::demo();
+    let render = |threads: usize| {
+        // 36 scanlines are split into several tiles
+        let camera = camera::Camera::builder(64, 10).samples_per_pixel(2).filter(filter::FilterType::Mitchell).build().unwrap();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+
+        pool.install(|| render::Render::render_to_buffer(&scene, &camera))
+    };
+
+    let single = render(1);
+    let multi = render(4);
+
+    for (a, b) in single.pixels().iter().zip(multi.pixels()) {
+        assert_eq!(a.red.to_bits(), b.red.to_bits());
+        assert_eq!(a.blue.to_bits(), b.blue.to_bits());
+    }
+}
+
+#[test]
+fn test_aabb(){
+    let a = aabb::Aabb::from_points(vector::Vec3::new(1.0, 2.0, 3.0), vector::Vec3::new(-1.0, 0.0, 1.0));
+    let b = aabb::Aabb::from_points(vector::Vec3::new(2.0, 0.0, 0.0), vector::Vec3::new(3.0, 1.0, 1.0));
+    let both = aabb::Aabb::surrounding(&a, &b);
+
+    assert_approx_eq!(a.x.min, -1.0);
+    assert_approx_eq!(a.surface_area(), 2.0 * (4.0 + 4.0 + 4.0));
+    assert_approx_eq!(both.x.max, 3.0);
+    assert_eq!(both.longest_axis(), 0);
+    assert_approx_eq!(aabb::Aabb::empty().surface_area(), 0.0);
+    assert!(aabb::Aabb::empty().is_empty());
+
+    let origin = vector::Vec3::new(0.0, 1.0, -5.0);
+    assert!(a.hit(&ray::Ray::new(origin, vector::Vec3::new(0.0, 0.0, 1.0)), 0.0, f64::MAX));
+    assert!(!a.hit(&ray::Ray::new(origin, vector::Vec3::new(0.0, 0.0, -1.0)), 0.0, f64::MAX));
+    assert!(!a.hit(&ray::Ray::new(origin, vector::Vec3::new(0.0, 0.0, 1.0)), 0.0, 5.0));
+    assert!(!a.hit(&ray::Ray::new(origin, vector::Vec3::new(0.0, 1.0, 0.0)), 0.0, f64::MAX));
+}
+
+#[test]
+fn test_bvh_matches_linear_search(){
+    use rand::{Rng, SeedableRng};
+
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let mut rng = rand_pcg::Pcg64Mcg::seed_from_u64(3);
+    let random_point = |rng: &mut rand_pcg::Pcg64Mcg| vector::Vec3::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0));
+
+    let spheres: Vec<(vector::Vec3, f64)> = (0..2000).map(|_| (random_point(&mut rng), rng.gen_range(0.05..0.4))).collect();
+    let world = bvh::Bvh::new(spheres.iter().map(|(center, radius)| sphere::Sphere::new(*center, *radius, material)).collect());
+    let linear: Vec<sphere::Sphere> = spheres.iter().map(|(center, radius)| sphere::Sphere::new(*center, *radius, material)).collect();
+
+    assert_eq!(world.len(), 2000);
+    assert!(world.bounding_box().x.min >= -10.4 && world.bounding_box().x.max <= 10.4);
+
+    use hittable::Hittable;
+    let mut hits = 0;
+    for _ in 0..500 {
+        // Rays from around the spheres aimed inside of the cloud
+        let origin = random_point(&mut rng) * 2.0;
+        let ray = ray::Ray::new(origin, random_point(&mut rng) - origin);
+
+        let expected = linear.iter().filter_map(|sphere| sphere.hit(&ray, 0.001, f64::MAX)).map(|hit| hit.t).fold(f64::INFINITY, f64::min);
+        match world.hit(&ray, 0.001, f64::MAX) {
+            Some(hit) => {
+                assert_approx_eq!(hit.t, expected);
+                hits += 1;
+            }
+            None => assert!(expected.is_infinite()),
+        }
+    }
+    assert!(hits > 50);
+
+    let empty: bvh::Bvh<sphere::Sphere> = bvh::Bvh::new(Vec::new());
+    assert!(empty.hit(&ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).is_none());
+}
+
+#[test]
+fn test_hittable_list_of_mixed_objects(){
+    use hittable::Hittable;
+
+    // Primitive defined outside of the crate modules, the integrator works with it unchanged.
+    struct Floor{ material: material::Material }
+
+    impl hittable::Hittable for Floor{
+        fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> Option<hit_record::HitRecord> {
+            let t = (-1.0 - r.origin().y()) / r.direction().y();
+            if !(t_min..t_max).contains(&t) {
+                return None
+            }
+
+            Some(hit_record::HitRecord::new(t, r.at(t), vector::Vec3::new(0.0, 1.0, 0.0), true, self.material))
+        }
+
+        fn bounding_box(&self) -> aabb::Aabb {
+            aabb::Aabb::from_points(vector::Vec3::new(-10.0, -1.0, -10.0), vector::Vec3::new(10.0, -1.0, 10.0)).pad(0.001)
+        }
+    }
+
+    let black = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.0, 0.0, 0.0)));
+    let mut list = hittable::HittableList::new();
+    list.add(Floor { material: black });
+    list.add(sphere::Sphere::new(vector::Vec3::new(0.0, 0.0, -3.0), 0.5, black));
+
+    assert_eq!(list.len(), 2);
+    assert_approx_eq!(list.bounding_box().x.max, 10.0);
+
+    let down = ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, -1.0, 0.0));
+    let ahead = ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, -1.0));
+    assert_approx_eq!(list.hit(&down, 0.001, f64::MAX).unwrap().t, 1.0);
+    assert_approx_eq!(list.hit(&ahead, 0.001, f64::MAX).unwrap().t, 2.5);
+
+    // Both kinds of objects end up in the scene BVH and block the sky.
+    let scene = scene::Scene::new(list);
+    let intensity = interval::Interval::new(0.001, f64::MAX);
+    let mut rng = sampler::IndependentSampler::new(0);
+    assert_approx_eq!(ray::Ray::ray_color(&down, intensity, &scene, 10, &mut rng).blue, 0.0);
+    assert_approx_eq!(ray::Ray::ray_color(&ahead, intensity, &scene, 10, &mut rng).blue, 0.0);
+}
+
+#[test]
+fn test_plane_hit_and_front_face(){
+    use hittable::Hittable;
+
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let plane = plane::Plane::new(vector::Vec3::new(0.0, -1.0, 0.0), vector::Vec3::new(0.0, 2.0, 0.0), material);
+
+    let from_above = plane.hit(&ray::Ray::new(vector::Vec3::new(3.0, 1.0, 0.0), vector::Vec3::new(0.0, -1.0, 0.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(from_above.t, 2.0);
+    assert!(from_above.front_face);
+    assert_approx_eq!(from_above.normal.y(), 1.0);
+
+    // Seen from below the normal is flipped towards the ray
+    let from_below = plane.hit(&ray::Ray::new(vector::Vec3::new(0.0, -3.0, 0.0), vector::Vec3::new(1.0, 1.0, 0.0)), 0.001, f64::MAX).unwrap();
+    assert!(!from_below.front_face);
+    assert_approx_eq!(from_below.normal.y(), -1.0);
+
+    // UV follow distance along the plane
+    let uv_distance = ((from_above.u - from_below.u).powi(2) + (from_above.v - from_below.v).powi(2)).sqrt();
+    assert_approx_eq!(uv_distance, 1.0);
+
+    assert!(plane.hit(&ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(1.0, 0.0, 0.0)), 0.001, f64::MAX).is_none());
+    assert!(!plane.bounding_box().is_finite());
+}
+
+#[test]
+fn test_disk_and_quad_hits(){
+    use hittable::Hittable;
+
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let disk = disk::Disk::new(vector::Vec3::new(0.0, 0.0, -2.0), vector::Vec3::new(0.0, 0.0, 1.0), 1.0, material);
+    let quad = quad::Quad::new(vector::Vec3::new(-1.0, -1.0, -3.0), vector::Vec3::new(2.0, 0.0, 0.0), vector::Vec3::new(0.0, 4.0, 0.0), material);
+    let towards = |x: f64, y: f64| ray::Ray::new(vector::Vec3::new(x, y, 0.0), vector::Vec3::new(0.0, 0.0, -1.0));
+
+    let center = disk.hit(&towards(0.0, 0.0), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(center.t, 2.0);
+    assert!(center.front_face);
+    assert_approx_eq!(center.u, 0.5);
+    assert_approx_eq!(center.v, 0.5);
+    assert!(disk.hit(&towards(0.8, 0.8), 0.001, f64::MAX).is_none());
+    assert_approx_eq!(disk.bounding_box().x.max, 1.0);
+
+    let corner = quad.hit(&towards(0.5, 2.0), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(corner.t, 3.0);
+    assert_approx_eq!(corner.u, 0.75);
+    assert_approx_eq!(corner.v, 0.75);
+    assert!(quad.hit(&towards(1.5, 0.0), 0.001, f64::MAX).is_none());
+    assert!(quad.bounding_box().is_finite());
+
+    // Planes are left out of the tree, but still found by the BVH
+    let mut list = hittable::HittableList::new();
+    list.add(quad);
+    list.add(plane::Plane::new(vector::Vec3::new(0.0, 0.0, -10.0), vector::Vec3::new(0.0, 0.0, 1.0), material));
+    let world = bvh::Bvh::new(list.into_objects());
+    assert_approx_eq!(world.hit(&towards(0.5, 2.0), 0.001, f64::MAX).unwrap().t, 3.0);
+    assert_approx_eq!(world.hit(&towards(5.0, 2.0), 0.001, f64::MAX).unwrap().t, 10.0);
+}
+
+#[test]
+fn test_scene_file_flat_objects(){
+    let source = r#"
+[camera]
+width = 16
+
+[materials.white]
+type = "lambertian"
+albedo = [0.7, 0.7, 0.7]
+
+[[objects]]
+type = "quad"
+origin = [-1.0, -1.0, -2.0]
+u = [2.0, 0.0, 0.0]
+v = [0.0, 2.0, 0.0]
+material = "white"
+
+[[objects]]
+type = "disk"
+center = [0.0, 1.0, -2.0]
+normal = [0.0, -1.0, 0.0]
+radius = 0.5
+material = "white"
+
+[[objects]]
+type = "triangle"
+vertices = [[0.0, 0.0, -1.0], [1.0, 0.0, -1.0], [0.0, 1.0, -1.0]]
+normals = [[0.0, 0.0, 1.0], [0.0, 0.0, 1.0], [0.0, 0.0, 1.0]]
+uvs = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]
+material = "white"
+"#;
+    assert_eq!(scene_file::parse(source).unwrap().scene.world.len(), 3);
+
+    let broken = source.replace("v = [0.0, 2.0, 0.0]", "v = [4.0, 0.0, 0.0]");
+    let message = scene_file::parse(&broken).err().unwrap().to_string();
+    assert!(message.contains("line 13"), "{}", message);
+
+    // Triangles with vertices on one line or in one point have no area
+    for vertices in ["[[0.0, 0.0, -1.0], [1.0, 0.0, -1.0], [2.0, 0.0, -1.0]]", "[[0.0, 0.0, -1.0], [0.0, 0.0, -1.0], [0.0, 1.0, -1.0]]"] {
+        let broken = source.replace("[[0.0, 0.0, -1.0], [1.0, 0.0, -1.0], [0.0, 1.0, -1.0]]", vertices);
+        let message = scene_file::parse(&broken).err().unwrap().to_string();
+        assert!(message.contains("line 25"), "{}", message);
+    }
+}
+
+#[test]
+fn test_triangle_hit(){
+    use hittable::Hittable;
+
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let triangle = triangle::Triangle::new(
+        vector::Vec3::new(0.0, 0.0, -2.0),
+        vector::Vec3::new(1.0, 0.0, -2.0),
+        vector::Vec3::new(0.0, 1.0, -2.0),
+        material,
+    );
+    let towards = |x: f64, y: f64, z: f64| ray::Ray::new(vector::Vec3::new(x, y, z), vector::Vec3::new(0.0, 0.0, -1.0));
+
+    let hit = triangle.hit(&towards(0.25, 0.5, 0.0), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 2.0);
+    assert!(hit.front_face);
+    assert_approx_eq!(hit.normal.z(), 1.0);
+    assert_approx_eq!(hit.point.x(), 0.25);
+    // Without texture coordinates UV are the barycentric weights of the second and third vertex
+    assert_approx_eq!(hit.u, 0.25);
+    assert_approx_eq!(hit.v, 0.5);
+
+    assert!(triangle.hit(&towards(0.6, 0.6, 0.0), 0.001, f64::MAX).is_none());
+    assert!(triangle.hit(&towards(0.25, 0.5, 0.0), 0.001, 1.5).is_none());
+    assert!(triangle.hit(&towards(0.25, 0.5, -3.0), 0.001, f64::MAX).is_none());
+
+    // Back side
+    let back = ray::Ray::new(vector::Vec3::new(0.25, 0.25, -4.0), vector::Vec3::new(0.0, 0.0, 1.0));
+    let hit = triangle.hit(&back, 0.001, f64::MAX).unwrap();
+    assert!(!hit.front_face);
+    assert_approx_eq!(hit.normal.z(), -1.0);
+
+    // Vertex attributes are interpolated
+    let smooth = triangle
+        .with_normals([vector::Vec3::new(-1.0, 0.0, 1.0), vector::Vec3::new(1.0, 0.0, 1.0), vector::Vec3::new(-1.0, 0.0, 1.0)])
+        .with_uvs([(0.0, 0.0), (2.0, 0.0), (0.0, 4.0)]);
+    let hit = smooth.hit(&towards(0.5, 0.25, 0.0), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.normal.x(), 0.0);
+    assert_approx_eq!(hit.normal.z(), 1.0);
+    assert_approx_eq!(hit.u, 1.0);
+    assert_approx_eq!(hit.v, 1.0);
+}
+
+#[test]
+fn test_triangles_are_watertight(){
+    use hittable::Hittable;
+    use rand::{Rng, SeedableRng};
+
+    // Flat quad split along an awkward diagonal, rays aimed exactly at the shared edge must hit one of the halves.
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let a = vector::Vec3::new(-0.3, -0.7, -1.3);
+    let b = vector::Vec3::new(1.1, -0.2, -2.9);
+    let c = vector::Vec3::new(0.9, 1.3, -2.1);
+    let d = a + c - b;
+    let mut list = hittable::HittableList::new();
+    list.add(triangle::Triangle::new(a, b, c, material));
+    list.add(triangle::Triangle::new(a, c, d, material));
+
+    let mut rng = rand_pcg::Pcg64Mcg::seed_from_u64(5);
+    for _ in 0..10000 {
+        let s: f64 = rng.gen();
+        let target = a + (c - a) * s;
+        let origin = vector::Vec3::new(rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0), rng.gen_range(0.0..2.0));
+
+        assert!(list.hit(&ray::Ray::new(origin, target - origin), 0.001, f64::MAX).is_some());
+    }
+}
+
+#[test]
+fn test_obj_import(){
+    use hittable::Hittable;
+
+    let directory = std::env::temp_dir().join(format!("rt_obj_test_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("model.mtl"), "\
+newmtl clay
+Kd 0.8 0.3 0.2
+Ks 0.1 0.1 0.1
+
+newmtl chrome
+Kd 0.1 0.1 0.1
+Ks 0.9 0.9 0.9
+Ns 1000
+
+newmtl window
+Kd 1 1 1
+Ni 1.45
+d 0.2
+").unwrap();
+    std::fs::write(directory.join("model.obj"), "\
+mtllib model.mtl
+v -1 -1 -2
+v 1 -1 -2
+v 1 1 -2
+v -1 1 -2
+v -1 -1 -5
+v 1 -1 -5
+v 0 1 -5
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+g front
+usemtl clay
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g back
+usemtl chrome
+f 5 6 7
+g side
+usemtl window
+f 5 1 4
+").unwrap();
+
+    let default = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let meshes = obj::load(&directory.join("model.obj"), default).unwrap();
+
+    let names: Vec<&str> = meshes.iter().map(|mesh| mesh.name()).collect();
+    assert_eq!(names, ["front", "back", "side"]);
+    // Quad face is split into two triangles
+    assert_eq!(meshes[0].len(), 2);
+    assert_eq!(meshes[1].len(), 1);
+
+    let hit = meshes[0].hit(&ray::Ray::new(vector::Vec3::new(0.5, 0.5, 0.0), vector::Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 2.0);
+    assert_approx_eq!(hit.u, 0.75);
+    assert_approx_eq!(hit.v, 0.75);
+    assert!(matches!(hit.material, material::Material::Lambertian(_)));
+
+    let hit = meshes[1].hit(&ray::Ray::new(vector::Vec3::new(0.0, 0.0, 0.0), vector::Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert!(matches!(hit.material, material::Material::Metallic(_)));
+    assert!(matches!(meshes[2].triangles()[0].hit(&ray::Ray::new(vector::Vec3::new(-2.0, -0.5, -3.0), vector::Vec3::new(1.0, 0.0, 0.0)), 0.001, f64::MAX).unwrap().material, material::Material::Glass(_)));
+
+    // Scene files refer to models relative to their own location
+    std::fs::write(directory.join("scene.toml"), "\
+[camera]
+width = 16
+
+[[objects]]
+type = \"obj\"
+path = \"model.obj\"
+").unwrap();
+    assert_eq!(scene_file::load(&directory.join("scene.toml")).unwrap().scene.world.len(), 3);
+
+    let missing = scene_file::parse_in("[camera]\nwidth = 16\n\n[[objects]]\ntype = \"obj\"\npath = \"missing.obj\"\n", &directory);
+    assert!(missing.err().unwrap().to_string().contains("line 6"));
+
+    // Without its material library the model is still loaded, faces get the default material
+    let gray = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    std::fs::write(directory.join("lost.obj"), "mtllib lost.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl clay\nf 1 2 3\n").unwrap();
+    std::fs::write(directory.join("broken.mtl"), "newmtl\nKd x y z\n").unwrap();
+    std::fs::write(directory.join("broken.obj"), "mtllib broken.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl clay\nf 1 2 3\n").unwrap();
+    for name in ["lost.obj", "broken.obj"] {
+        let meshes = obj::load(&directory.join(name), gray).unwrap();
+        let down = ray::Ray::new(vector::Vec3::new(0.2, 0.2, 1.0), vector::Vec3::new(0.0, 0.0, -1.0));
+        let hit = meshes[0].triangles()[0].hit(&down, 0.001, f64::MAX).unwrap();
+        let reflected = material::Scatterable::evaluate(&hit.material, &down, &hit, vector::Vec3::new(0.0, 0.0, 1.0));
+        assert_approx_eq!(reflected.red as f64, 0.5 / std::f64::consts::PI, 1e-6);
+    }
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_gltf_import(){
+    use hittable::Hittable;
+
+    let directory = std::env::temp_dir().join(format!("rt_gltf_test_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    // Unit quad facing +Z with texture coordinates and two triangles
+    let mut buffer: Vec<u8> = Vec::new();
+    for value in [-1.0f32, -1.0, 0.0, 1.0, -1.0, 0.0, 1.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0] {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+    for index in [0u16, 1, 2, 0, 2, 3] {
+        buffer.extend_from_slice(&index.to_le_bytes());
+    }
+    std::fs::write(directory.join("quad.bin"), &buffer).unwrap();
+
+    // Red left half, white right half
+    image::RgbImage::from_raw(2, 1, vec![255, 0, 0, 255, 255, 255]).unwrap().save(directory.join("texture.png")).unwrap();
+
+    std::fs::write(directory.join("quad.gltf"), r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0, 2] }],
+        "nodes": [
+            { "translation": [0, 0, -3], "children": [1] },
+            { "mesh": 0, "scale": [2, 2, 2] },
+            { "camera": 0, "translation": [0, 1, 5] }
+        ],
+        "cameras": [{ "type": "perspective", "perspective": { "yfov": 0.5, "aspectRatio": 1.5, "znear": 0.1 } }],
+        "meshes": [{ "name": "quad", "primitives": [{ "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "indices": 2, "material": 0 }] }],
+        "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [1, 0.5, 0.5, 1], "metallicFactor": 0.0, "roughnessFactor": 0.8, "baseColorTexture": { "index": 0 } } }],
+        "textures": [{ "source": 0 }],
+        "images": [{ "uri": "texture.png" }],
+        "buffers": [{ "uri": "quad.bin", "byteLength": 92 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+            { "buffer": 0, "byteOffset": 48, "byteLength": 32 },
+            { "buffer": 0, "byteOffset": 80, "byteLength": 12 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3", "min": [-1, -1, 0], "max": [1, 1, 0] },
+            { "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC2" },
+            { "bufferView": 2, "componentType": 5123, "count": 6, "type": "SCALAR" }
+        ]
+    }"#).unwrap();
+
+    let gltf = gltf_file::load(&directory.join("quad.gltf")).unwrap();
+    assert_eq!(gltf.meshes.len(), 1);
+    assert_eq!(gltf.meshes[0].name(), "quad");
+    assert_eq!(gltf.meshes[0].len(), 2);
+
+    // Node transforms are applied: quad is scaled to 4x4 and moved 3 units away
+    let bounds = gltf.meshes[0].bounding_box();
+    assert_approx_eq!(bounds.x.max, 2.0, 1e-3);
+    assert_approx_eq!(bounds.z.min, -3.0, 1e-3);
+
+    let hit = gltf.meshes[0].hit(&ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 3.0);
+    assert!(hit.front_face);
+    match hit.material {
+        material::Material::Pbr(pbr) => {
+            // Base color factor times texture, halfway between red and white texels
+            assert_approx_eq!(pbr.base_color.red, 1.0, 1e-3);
+            assert_approx_eq!(pbr.base_color.green, 0.25, 1e-3);
+            assert_approx_eq!(pbr.roughness, 0.8, 1e-6);
+        }
+        _ => panic!("glTF material should be PBR"),
+    }
+
+    let camera = gltf.camera.unwrap();
+    assert_approx_eq!(camera.look_from.y(), 1.0);
+    assert_approx_eq!(camera.look_from.z(), 5.0);
+    assert_approx_eq!(camera.look_at.z(), 4.0);
+    assert_approx_eq!(camera.vfov, 0.5f64.to_degrees(), 1e-5);
+
+    // glTF file can be used as the scene, its camera is used
+    let description = scene_file::load(&directory.join("quad.gltf")).unwrap();
+    let camera = description.camera.image_width(150).build().unwrap();
+    assert_eq!(camera.image_height, 100);
+    assert_eq!(description.scene.world.len(), 1);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_ply_ascii(){
+    use hittable::Hittable;
+
+    let ply = b"ply
+format ascii 1.0
+comment quad with a red and a blue corner
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+-1 -1 -2 255 0 0
+1 -1 -2 255 255 255
+1 1 -2 0 0 255
+-1 1 -2 255 255 255
+4 0 1 2 3
+0 1
+";
+    let base = material::Material::Pbr(material::Pbr::new(palette::Srgb::new(0.5, 0.5, 0.5), 0.0, 1.0));
+    let triangles = ply::parse(ply, base).unwrap();
+    assert_eq!(triangles.len(), 2);
+
+    let mesh = mesh::TriangleMesh::new("quad", triangles);
+    let hit = mesh.hit(&ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 2.0);
+    assert!(hit.front_face);
+
+    // Center lies on the diagonal between the red and the blue corner, vertex colors replace the material color
+    match hit.material {
+        material::Material::Pbr(pbr) => {
+            assert_approx_eq!(pbr.base_color.red, 0.5, 1e-5);
+            assert_approx_eq!(pbr.base_color.green, 0.0, 1e-5);
+            assert_approx_eq!(pbr.base_color.blue, 0.5, 1e-5);
+        }
+        _ => panic!("vertex colors should keep the material type"),
+    }
+
+    assert!(ply::parse(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n3 0 1 2\n", base).is_err());
+    assert!(ply::parse(b"solid cube\n", base).is_err());
+
+    // Vertex indices have to be whole numbers which fit their type
+    let triangle = |index_type: &str, face: &str| {
+        format!("ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\n\
+            property list uchar {} vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n{}\n", index_type, face)
+    };
+    assert_eq!(ply::parse(triangle("int", "3 0 1 2").as_bytes(), base).unwrap().len(), 1);
+    assert_eq!(ply::parse(triangle("float", "3 0.0 1 2e0").as_bytes(), base).unwrap().len(), 1);
+    for (index_type, face) in [("int", "3 0 1.5 2"), ("int", "3 0 -1 2"), ("int", "3 0 nan 2"), ("int", "3 0 1 4294967298"),
+        ("uchar", "3 0 1 256"), ("float", "3 0 1.5 2"), ("float", "3 0 -1 2"), ("float", "3 0 nan 2"), ("int", "2.5 0 1 2")] {
+        assert!(ply::parse(triangle(index_type, face).as_bytes(), base).is_err(), "{} {}", index_type, face);
+    }
+}
+
+#[test]
+fn test_ply_binary(){
+    use hittable::Hittable;
+
+    // Triangle facing +Z with vertex normals tilted towards +X, in both byte orders
+    let binary = |big_endian: bool| {
+        let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut data = format!("ply\r\nformat {} 1.0\r\nelement vertex 3\r\nproperty double x\r\nproperty double y\r\nproperty double z\r\nproperty float nx\r\nproperty float ny\r\nproperty float nz\r\nelement face 1\r\nproperty list uchar uint vertex_indices\r\nproperty ushort flags\r\nend_header\r\n", format).into_bytes();
+
+        for position in [[-1.0f64, -1.0, -3.0], [1.0, -1.0, -3.0], [0.0, 1.0, -3.0]] {
+            for value in position {
+                data.extend_from_slice(&if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+            for value in [1.0f32, 0.0, 1.0] {
+                data.extend_from_slice(&if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+        }
+
+        data.push(3);
+        for index in [0u32, 1, 2] {
+            data.extend_from_slice(&if big_endian { index.to_be_bytes() } else { index.to_le_bytes() });
+        }
+        data.extend_from_slice(&7u16.to_le_bytes());
+
+        data
+    };
+
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let ray = ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, -1.0));
+
+    for big_endian in [false, true] {
+        let triangles = ply::parse(&binary(big_endian), material).unwrap();
+        assert_eq!(triangles.len(), 1);
+        assert_approx_eq!(triangles[0].vertices()[2].y(), 1.0);
+
+        let hit = triangles[0].hit(&ray, 0.001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 3.0);
+        assert_approx_eq!(hit.normal.x(), 0.5f64.sqrt());
+        assert_approx_eq!(hit.normal.z(), 0.5f64.sqrt());
+    }
+
+    // Truncated body
+    let mut truncated = binary(false);
+    truncated.truncate(truncated.len() - 4);
+    assert!(ply::parse(&truncated, material).is_err());
+
+    // Corrupt counts fail to read instead of reserving memory for them
+    for format in ["binary_little_endian", "ascii"] {
+        let huge = format!("ply\nformat {} 1.0\nelement vertex 4000000000\nproperty float x\nproperty float y\nproperty float z\nelement face 4000000000\nproperty list uchar int vertex_indices\nend_header\n", format);
+        assert!(ply::parse(huge.as_bytes(), material).is_err());
+    }
+
+    let directory = std::env::temp_dir().join(format!("rt_ply_test_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("tilted.ply"), binary(false)).unwrap();
+
+    let mesh = ply::load(&directory.join("tilted.ply"), material).unwrap();
+    assert_eq!(mesh.name(), "tilted");
+    assert_eq!(mesh.len(), 1);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_stl_import(){
+    use hittable::Hittable;
+
+    let directory = std::env::temp_dir().join(format!("rt_stl_test_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    std::fs::write(directory.join("ascii.stl"), "\
+solid square
+facet normal 0 0 1
+  outer loop
+    vertex -1 -1 -2
+    vertex 1 -1 -2
+    vertex 1 1 -2
+  endloop
+endfacet
+facet normal 0 0 1
+  outer loop
+    vertex -1 -1 -2
+    vertex 1 1 -2
+    vertex -1 1 -2
+  endloop
+endfacet
+endsolid square
+").unwrap();
+
+    // Binary STL: 80 byte header, triangle count and 50 bytes per triangle
+    let mut binary = vec![0u8; 80];
+    binary.extend_from_slice(&3u32.to_le_bytes());
+    for triangle in [[[-1.0f32, -1.0, -4.0], [1.0, -1.0, -4.0], [1.0, 1.0, -4.0]], [[-1.0, -1.0, -4.0], [1.0, 1.0, -4.0], [-1.0, 1.0, -4.0]], [[0.0, 0.0, -4.0], [0.0, 0.0, -4.0], [1.0, 0.0, -4.0]]] {
+        for value in [0.0f32, 0.0, 1.0].iter().chain(triangle.iter().flatten()) {
+            binary.extend_from_slice(&value.to_le_bytes());
+        }
+        binary.extend_from_slice(&0u16.to_le_bytes());
+    }
+    std::fs::write(directory.join("binary.stl"), &binary).unwrap();
+
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let ray = ray::Ray::new(vector::Vec3::new(0.3, 0.2, 0.0), vector::Vec3::new(0.0, 0.0, -1.0));
+
+    let ascii = stl::load(&directory.join("ascii.stl"), material).unwrap();
+    assert_eq!(ascii.name(), "ascii");
+    assert_eq!(ascii.len(), 2);
+    assert_approx_eq!(ascii.hit(&ray, 0.001, f64::MAX).unwrap().t, 2.0);
+
+    // Degenerate triangle is dropped
+    let binary = stl::load(&directory.join("binary.stl"), material).unwrap();
+    assert_eq!(binary.len(), 2);
+    assert!(binary.hit(&ray, 0.001, f64::MAX).unwrap().front_face);
+    assert_approx_eq!(binary.hit(&ray, 0.001, f64::MAX).unwrap().t, 4.0);
+
+    // Both formats can be used in scene files, next to the scene file
+    std::fs::write(directory.join("scene.toml"), "\
+[camera]
+width = 16
+
+[materials.white]
+type = \"lambertian\"
+albedo = [0.9, 0.9, 0.9]
+
+[[objects]]
+type = \"stl\"
+path = \"binary.stl\"
+material = \"white\"
+
+[[objects]]
+type = \"ply\"
+path = \"missing.ply\"
+").unwrap();
+    match scene_file::load(&directory.join("scene.toml")) {
+        Err(error) => assert!(error.to_string().contains("line 15"), "{}", error),
+        Ok(_) => panic!("missing PLY file should fail"),
+    }
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_mat4_transform(){
+    use vector::{Vec3, Mat4, Transform};
+
+    // Counter-clockwise around z turns x into y
+    let rotated = Mat4::rotate(Vec3::new(0.0, 0.0, 1.0), 90.0).transform_vector(Vec3::new(1.0, 0.0, 0.0));
+    assert_approx_eq!(rotated.x(), 0.0);
+    assert_approx_eq!(rotated.y(), 1.0);
+
+    // Compose applies the right matrix first
+    let m = Mat4::translate(Vec3::new(1.0, 2.0, 3.0)).compose(&Mat4::scale(Vec3::new(2.0, 2.0, 2.0)));
+    let p = m.transform_point(Vec3::new(1.0, 1.0, 1.0));
+    assert_approx_eq!(p.x(), 3.0);
+    assert_approx_eq!(p.z(), 5.0);
+    assert_eq!(m.transform_vector(Vec3::new(1.0, 0.0, 0.0)), Vec3::new(2.0, 0.0, 0.0));
+
+    let general = Mat4::rotate(Vec3::new(1.0, 2.0, 3.0), 37.0) * m * Mat4::scale(Vec3::new(1.0, -3.0, 0.5));
+    let identity = general * general.inverse().unwrap();
+    for row in 0..4 {
+        for column in 0..4 {
+            assert_approx_eq!(identity.m[row][column], if row == column { 1.0 } else { 0.0 });
+        }
+    }
+    assert!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    assert!(Transform::scale(Vec3::new(1.0, 0.0, 1.0)).is_none());
+
+    // `then` applies the transforms in the written order
+    let transform = Transform::scale(Vec3::new(2.0, 1.0, 1.0)).unwrap()
+        .then(&Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 90.0))
+        .then(&Transform::translate(Vec3::new(0.0, 0.0, -5.0)));
+    let p = transform.point(Vec3::new(1.0, 0.0, 0.0));
+    assert_approx_eq!(p.x(), 0.0);
+    assert_approx_eq!(p.z(), -7.0);
+
+    let back = transform.inverse().point(p);
+    assert_approx_eq!(back.x(), 1.0);
+    assert_approx_eq!(back.z(), 0.0);
+
+    // Normal of the stretched plane x + y = 0 leans towards the axis that wasn't stretched
+    let stretch = Transform::scale(Vec3::new(2.0, 1.0, 1.0)).unwrap();
+    let normal = stretch.normal(Vec3::new(1.0, 1.0, 0.0));
+    assert_approx_eq!(normal.dot(&stretch.vector(Vec3::new(1.0, -1.0, 0.0))), 0.0);
+    assert_approx_eq!(normal.length(), 1.0);
+
+    assert!(Transform::scale(Vec3::new(-1.0, 1.0, 1.0)).unwrap().is_mirroring());
+    assert!(!transform.is_mirroring());
+}
+
+#[test]
+fn test_instances(){
+    use std::sync::Arc;
+    use hittable::Hittable;
+    use vector::{Vec3, Transform};
+
+    let material = material::Material::Lambertian(material::Lambertian::new(palette::Srgb::new(0.5, 0.5, 0.5)));
+    let sphere: Arc<dyn Hittable> = Arc::new(sphere::Sphere::new(Vec3::default(), 1.0, material));
+
+    // Ellipsoid stretched along x, 5 units in front of the camera
+    let ellipsoid = instance::Instance::new(sphere.clone(), Transform::scale(Vec3::new(2.0, 1.0, 1.0)).unwrap().then(&Transform::translate(Vec3::new(0.0, 0.0, -5.0))));
+
+    let hit = ellipsoid.hit(&ray::Ray::new(Vec3::default(), Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 4.0);
+    assert_approx_eq!(hit.point.z(), -4.0);
+    assert_approx_eq!(hit.normal.z(), 1.0);
+    assert!(hit.front_face);
+
+    let hit = ellipsoid.hit(&ray::Ray::new(Vec3::new(10.0, 0.0, -5.0), Vec3::new(-2.0, 0.0, 0.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 4.0);
+    assert_approx_eq!(hit.point.x(), 2.0);
+    assert_approx_eq!(hit.normal.x(), 1.0);
+
+    let bounds = ellipsoid.bounding_box();
+    assert_approx_eq!(bounds.x.max, 2.0, 1e-6);
+    assert_approx_eq!(bounds.z.min, -6.0, 1e-6);
+
+    // Normal still points out of a mirrored instance
+    let mirrored = instance::Instance::new(sphere.clone(), Transform::scale(Vec3::new(-1.0, 1.0, 1.0)).unwrap().then(&Transform::translate(Vec3::new(0.0, 0.0, -5.0))));
+    let hit = mirrored.hit(&ray::Ray::new(Vec3::default(), Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert!(hit.front_face);
+    assert_approx_eq!(hit.normal.z(), 1.0);
+
+    // A thousand instances share one sphere
+    let mut world = hittable::HittableList::new();
+    for i in 0..1000 {
+        let (x, y) = ((i % 40) as f64 * 3.0, (i / 40) as f64 * 3.0);
+        world.add(instance::Instance::new(sphere.clone(), Transform::translate(Vec3::new(x, y, -10.0))));
+    }
+    assert_eq!(Arc::strong_count(&sphere), 1003);
+
+    let world = bvh::Bvh::new(world.into_objects());
+    let hit = world.hit(&ray::Ray::new(Vec3::new(30.0, 27.0, 0.0), Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 9.0);
+    assert!(world.hit(&ray::Ray::new(Vec3::new(31.5, 27.0, 0.0), Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).is_none());
+}
+
+#[test]
+fn test_scene_file_transforms(){
+    use hittable::Hittable;
+
+    let directory = std::env::temp_dir().join(format!("rt_transform_test_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("square.obj"), "v -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\nf 1 2 3 4\n").unwrap();
+
+    let source = "\
+[camera]
+width = 16
+
+[materials.white]
+type = \"lambertian\"
+albedo = [0.9, 0.9, 0.9]
+
+[[objects]]
+type = \"sphere\"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = \"white\"
+transform = { scale = [1.0, 1.0, 0.5], translate = [0.0, 0.0, -4.0] }
+
+[[objects]]
+type = \"obj\"
+path = \"square.obj\"
+transform = { rotate = [0.0, 90.0, 0.0], translate = [10.0, 0.0, 0.0] }
+
+[[objects]]
+type = \"obj\"
+path = \"square.obj\"
+[objects.transform]
+scale = 2.0
+translate = [0.0, 10.0, -3.0]
+";
+    let description = scene_file::parse_in(source, &directory).unwrap();
+    let world = &description.scene.world;
+    assert_eq!(world.len(), 3);
+
+    // Flattened sphere
+    let hit = world.hit(&ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 3.5);
+
+    // Square turned to face +x, and scaled copy of the same square
+    let hit = world.hit(&ray::Ray::new(vector::Vec3::new(20.0, 0.5, 0.5), vector::Vec3::new(-1.0, 0.0, 0.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 10.0);
+    assert!(hit.front_face);
+    let hit = world.hit(&ray::Ray::new(vector::Vec3::new(1.5, 11.5, 0.0), vector::Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::MAX).unwrap();
+    assert_approx_eq!(hit.t, 3.0);
+
+    let zero = scene_file::parse("[camera]\nwidth = 16\n\n[materials.white]\ntype = \"lambertian\"\nalbedo = [0.9, 0.9, 0.9]\n\n[[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 1.0\nmaterial = \"white\"\ntransform = { scale = 0.0 }\n");
+    let message = zero.err().unwrap().to_string();
+    assert!(message.contains("line 13") && message.contains("scale"), "{}", message);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_emissive_materials(){
+    use palette::Srgb;
+    use material::Scatterable;
+    use hittable::Hittable;
+
+    let intensity = interval::Interval::new(0.001, f64::MAX);
+    let mut rng = sampler::IndependentSampler::new(0);
+    let ahead = ray::Ray::new(vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, -1.0));
+
+    // Light seen directly gives its color and scatters nothing
+    let light = material::Material::DiffuseLight(material::DiffuseLight::new(Srgb::new(4.0, 2.0, 1.0)));
+    let mut world = hittable::HittableList::new();
+    world.add(sphere::Sphere::new(vector::Vec3::new(0.0, 0.0, -3.0), 1.0, light));
+    let scene = scene::Scene::new(world).with_environment(environment::Environment::Constant(Srgb::new(0.0, 0.0, 0.0)));
+
+    let color = ray::Ray::ray_color(&ahead, intensity, &scene, 10, &mut rng);
+    assert_approx_eq!(color.red, 4.0);
+    assert_approx_eq!(color.green, 2.0);
+    assert_approx_eq!(color.blue, 1.0);
+
+    let hit = scene.world.hit(&ahead, 0.001, f64::MAX).unwrap();
+    assert!(light.scatter(&ahead, &hit, &mut rng).is_none());
+
+    // Only the front side glows, unless the light is two sided
+    let mut back = hit_record::HitRecord::new(2.0, vector::Vec3::default(), vector::Vec3::new(0.0, 0.0, 1.0), false, light);
+    assert_approx_eq!(light.emitted(&ahead, &back).red, 0.0);
+    back.material = material::Material::DiffuseLight(material::DiffuseLight::new(Srgb::new(4.0, 2.0, 1.0)).with_two_sided(true));
+    assert_approx_eq!(back.material.emitted(&ahead, &back).red, 4.0);
+
+    // Diffuse ball inside a glowing shell: every bounce off the ball reaches the shell, so each path
+    // carries exactly albedo times emitted light.
+    let shell = material::Material::DiffuseLight(material::DiffuseLight::new(Srgb::new(1.0, 1.0, 1.0)).with_two_sided(true));
+    let gray = material::Material::Lambertian(material::Lambertian::new(Srgb::new(0.5, 0.5, 0.5)));
+    let mut world = hittable::HittableList::new();
+    world.add(sphere::Sphere::new(vector::Vec3::new(0.0, 0.0, -3.0), 1.0, gray));
+    world.add(sphere::Sphere::new(vector::Vec3::default(), 10.0, shell));
+    let scene = scene::Scene::new(world);
+
+    for _ in 0..100 {
+        assert_approx_eq!(ray::Ray::ray_color(&ahead, intensity, &scene, 10, &mut rng).green, 0.5, 1e-6);
+    }
+
+    // Emission of PBR materials adds to the scattered light
+    let glowing = material::Material::Pbr(material::Pbr::new(Srgb::new(0.5, 0.5, 0.5), 0.0, 1.0).with_emission(Srgb::new(2.0, 0.0, 0.0)));
+    let mut world = hittable::HittableList::new();
+    world.add(sphere::Sphere::new(vector::Vec3::new(0.0, 0.0, -3.0), 1.0, glowing));
+    let scene = scene::Scene::new(world).with_environment(environment::Environment::Constant(Srgb::new(0.0, 0.0, 0.0)));
+    let color = ray::Ray::ray_color(&ahead, intensity, &scene, 10, &mut rng);
+    assert_approx_eq!(color.red, 2.0);
+    assert_approx_eq!(color.green, 0.0);
+}
+
+#[test]
+fn test_scene_file_lights(){
+    let description = scene_file::load(std::path::Path::new("scenes/cornell.toml")).unwrap();
+    assert_approx_eq!(description.scene.environment.color(vector::Vec3::new(0.0, 1.0, 0.0)).blue, 0.0);
+    assert_eq!(description.scene.world.len(), 11);
+    assert_eq!(description.scene.lights.len(), 1);
+
+    let source = "[camera]\nwidth = 16\n\n[materials.lamp]\ntype = \"light\"\nemit = [1.0, -1.0, 1.0]\n";
+    let message = scene_file::parse(source).err().unwrap().to_string();
+    assert!(message.contains("line 6"), "{}", message);
+}
+
+#[test]
+fn test_environment(){
+    use palette::Srgb;
+    use vector::Vec3;
+
+    let constant = environment::Environment::Constant(Srgb::new(0.2, 0.3, 0.4));
+    assert_approx_eq!(constant.color(Vec3::new(1.0, 2.0, 3.0)).green, 0.3);
+
+    // Default sky: white below, blue above
+    let sky = environment::Environment::default();
+    assert_approx_eq!(sky.color(Vec3::new(0.0, -2.0, 0.0)).red, 1.0);
+    assert_approx_eq!(sky.color(Vec3::new(0.0, 2.0, 0.0)).red, 0.5);
+    assert_approx_eq!(sky.color(Vec3::new(1.0, 0.0, 0.0)).green, 0.85);
+
+    // Equirectangular map: left half red, right half bright blue
+    let pixels = (0..8 * 4).map(|i| if i % 8 < 4 { Srgb::new(1.0, 0.0, 0.0) } else { Srgb::new(0.0, 0.0, 10.0) }).collect();
+    let map = environment::EnvironmentMap::new(texture::ImageTexture::new(8, 4, pixels));
+
+    let (u, v) = map.uv(Vec3::new(0.0, 0.0, -1.0));
+    assert_approx_eq!(u, 0.5);
+    assert_approx_eq!(v, 0.5);
+    assert_approx_eq!(map.uv(Vec3::new(0.0, 5.0, 0.0)).1, 0.0);
+
+    assert_approx_eq!(map.color(Vec3::new(1.0, 0.0, 0.0)).blue, 10.0);
+    assert_approx_eq!(map.color(Vec3::new(-1.0, 0.0, 0.0)).red, 1.0);
+    assert_approx_eq!(map.color(Vec3::new(-1.0, 1.0, 0.0)).red, 1.0);
+
+    // Turned half way around and twice as bright
+    let turned = map.clone().with_rotation(180.0).with_intensity(2.0);
+    assert_approx_eq!(turned.color(Vec3::new(1.0, 0.0, 0.0)).red, 2.0);
+    assert_approx_eq!(turned.color(Vec3::new(-1.0, 0.0, 0.0)).blue, 20.0);
+
+    // Rays leaving the scene see the environment
+    let scene = scene::Scene::new(hittable::HittableList::new()).with_environment(environment::Environment::Map(map));
+    let mut rng = sampler::IndependentSampler::new(0);
+    let right = ray::Ray::new(Vec3::default(), Vec3::new(1.0, 0.0, 0.0));
+    assert_approx_eq!(ray::Ray::ray_color(&right, interval::Interval::new(0.001, f64::MAX), &scene, 10, &mut rng).blue, 10.0);
+}
+
+#[test]
+fn test_scene_file_environment(){
+    let directory = std::env::temp_dir().join(format!("rt_environment_test_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let image = image::Rgb32FImage::from_fn(8, 4, |x, _| if x < 4 { image::Rgb([1.0, 0.0, 0.0]) } else { image::Rgb([0.0, 0.0, 10.0]) });
+    image.save(directory.join("studio.exr")).unwrap();
+
+    let source = "[camera]\nwidth = 16\n\n[environment]\ntype = \"map\"\npath = \"studio.exr\"\nrotation = 180.0\nintensity = 0.5\n";
+    let description = scene_file::parse_in(source, &directory).unwrap();
+    let color = description.scene.environment.color(vector::Vec3::new(1.0, 0.0, 0.0));
+    assert_approx_eq!(color.red, 0.5);
+    assert_approx_eq!(color.blue, 0.0);
+
+    let gradient = scene_file::parse("[camera]\nwidth = 16\n\n[environment]\ntype = \"gradient\"\nbottom = [0.0, 0.0, 0.0]\ntop = [1.0, 1.0, 1.0]\n").unwrap();
+    assert_approx_eq!(gradient.scene.environment.color(vector::Vec3::new(0.0, 1.0, 0.0)).red, 1.0);
+
+    let missing = scene_file::parse_in("[camera]\nwidth = 16\n\n[environment]\ntype = \"map\"\npath = \"missing.hdr\"\n", &directory);
+    let message = missing.err().unwrap().to_string();
+    assert!(message.contains("line 6") && message.contains("environment map"), "{}", message);
+
+    // Radiance files keep values above one
+    let file = std::fs::File::create(directory.join("sun.hdr")).unwrap();
+    image::codecs::hdr::HdrEncoder::new(file).encode(&[image::Rgb([100.0, 50.0, 0.5]); 8 * 4], 8, 4).unwrap();
+    let map = environment::EnvironmentMap::load(&directory.join("sun.hdr")).unwrap();
+    assert_approx_eq!(map.color(vector::Vec3::new(0.0, 0.0, -1.0)).red, 100.0);
+    assert_approx_eq!(map.color(vector::Vec3::new(0.0, 0.0, -1.0)).blue, 0.5, 0.01);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_distribution(){
+    // Steps of width 1/4 with values 1, 3, 0 and 4, their average is 2
+    let steps = distribution::Distribution1D::new(&[1.0, 3.0, 0.0, 4.0]);
+    assert_eq!(steps.len(), 4);
+    assert_approx_eq!(steps.integral(), 2.0);
+
+    let (x, pdf, index) = steps.sample(0.3);
+    assert_eq!(index, 1);
+    assert_approx_eq!(x, 0.25 + 0.25 * (0.3 - 0.125) / 0.375);
+    assert_approx_eq!(pdf, 1.5);
+    assert_approx_eq!(steps.pdf(x), pdf);
+
+    // Empty step is skipped
+    let (x, pdf, index) = steps.sample(0.5);
+    assert_eq!(index, 3);
+    assert_approx_eq!(x, 0.75);
+    assert_approx_eq!(pdf, 2.0);
+    assert_approx_eq!(steps.pdf(0.6), 0.0);
+
+    // All zero function is sampled uniformly
+    let flat = distribution::Distribution1D::new(&[0.0, 0.0]);
+    assert_approx_eq!(flat.sample(0.7).0, 0.7);
+    assert_approx_eq!(flat.pdf(0.2), 1.0);
+
+    // 2D: samples follow the function, so its value over the density is always its average
+    let function = [0.0, 1.0, 2.0, 3.0, 0.0, 0.0, 8.0, 2.0, 1.0];
+    let grid = distribution::Distribution2D::new(&function, 3);
+    let mut rng = sampler::IndependentSampler::new(1);
+    for _ in 0..100 {
+        let ((u, v), pdf) = grid.sample(sampler::Sampler::get_2d(&mut rng));
+        assert_approx_eq!(grid.pdf(u, v), pdf);
+
+        let value = function[(v * 3.0) as usize * 3 + (u * 3.0) as usize];
+        assert_approx_eq!(value / pdf, 17.0 / 9.0);
+    }
+}
+
+#[test]
+fn test_environment_sampling(){
+    use palette::Srgb;
+    use vector::Vec3;
+
+    // Dim map with a small bright sun high in the sky
+    let pixels = (0..32 * 16).map(|i| {
+        let (x, y) = (i % 32, i / 32);
+        if (20..22).contains(&x) && (4..6).contains(&y) { Srgb::new(1000.0, 900.0, 800.0) } else { Srgb::new(0.1, 0.1, 0.1) }
+    }).collect();
+    let map = environment::EnvironmentMap::new(texture::ImageTexture::new(32, 16, pixels)).with_rotation(30.0);
+
+    let mut rng = sampler::IndependentSampler::new(2);
+    let mut at_sun = 0;
+    for _ in 0..200 {
+        let (direction, light, pdf) = map.sample(sampler::Sampler::get_2d(&mut rng)).unwrap();
+        assert_approx_eq!(direction.length(), 1.0);
+        assert_approx_eq!(map.pdf(direction), pdf, 1e-6 * pdf);
+        assert_approx_eq!(light.red, map.color(direction).red);
+
+        let (u, v) = map.uv(direction);
+        let back = map.direction(u, v);
+        assert_approx_eq!(back.x(), direction.x());
+        assert_approx_eq!(back.z(), direction.z());
+
+        if (20.0..22.0).contains(&(u * 32.0)) && (4.0..6.0).contains(&(v * 16.0)) {
+            at_sun += 1;
+        }
+    }
+    assert!(at_sun > 190);
+
+    // Density over the whole sphere adds up to one
+    let mut total = 0.0;
+    let count = 200_000;
+    for _ in 0..count {
+        total += map.pdf(Vec3::sample_unit_vec3(sampler::Sampler::get_2d(&mut rng))) * 4.0 * std::f64::consts::PI;
+    }
+    assert_approx_eq!(total / count as f64, 1.0, 0.05);
+
+    // Only maps are sampled
+    assert!(environment::Environment::sky().sample((0.5, 0.5)).is_none());
+    assert!(environment::Environment::sky().pdf(Vec3::new(0.0, 1.0, 0.0)).is_none());
+
+    // White diffuse floor lit by the sun: light sampling finds the sun, so a few hundred paths are close to the exact value
+    let mut irradiance = 0.0;
+    let (columns, rows) = (1024, 512);
+    for y in 0..rows / 2 {
+        let v = (y as f64 + 0.5) / rows as f64;
+        let theta = v * std::f64::consts::PI;
+        for x in 0..columns {
+            let u = (x as f64 + 0.5) / columns as f64;
+            let light = map.color(map.direction(u, v)).green as f64;
+            irradiance += light * theta.cos() * theta.sin() * 2.0 * std::f64::consts::PI.powi(2) / (columns * rows) as f64;
+        }
+    }
+
+    let white = material::Material::Lambertian(material::Lambertian::new(Srgb::new(1.0, 1.0, 1.0)));
+    let mut world = hittable::HittableList::new();
+    world.add(plane::Plane::new(Vec3::default(), Vec3::new(0.0, 1.0, 0.0), white));
+    let scene = scene::Scene::new(world).with_environment(environment::Environment::Map(map));
+
+    let down = ray::Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let paths = 400;
+    let mut total = 0.0;
+    for _ in 0..paths {
+        total += ray::Ray::ray_color(&down, interval::Interval::new(0.001, f64::MAX), &scene, 2, &mut rng).green as f64;
+    }
+    assert_approx_eq!(total / paths as f64, irradiance / std::f64::consts::PI, 0.03 * irradiance / std::f64::consts::PI);
+
+    // Smooth white varnish in a uniform white map reflects all light: mirror rays keep their full weight
+    let uniform = environment::EnvironmentMap::new(texture::ImageTexture::new(8, 4, vec![Srgb::new(1.0, 1.0, 1.0); 32]));
+    let varnish = material::Material::Pbr(material::Pbr::new(Srgb::new(1.0, 1.0, 1.0), 0.0, 0.0));
+    let mut world = hittable::HittableList::new();
+    world.add(plane::Plane::new(Vec3::default(), Vec3::new(0.0, 1.0, 0.0), varnish));
+    let scene = scene::Scene::new(world).with_environment(environment::Environment::Map(uniform));
+
+    let grazing = ray::Ray::new(Vec3::new(-3.0, 1.0, 0.0), Vec3::new(3.0, -1.0, 0.0));
+    let paths = 2000;
+    let mut total = 0.0;
+    for _ in 0..paths {
+        total += ray::Ray::ray_color(&grazing, interval::Interval::new(0.001, f64::MAX), &scene, 3, &mut rng).green as f64;
+    }
+    assert_approx_eq!(total / paths as f64, 1.0, 0.01);
+}
+
+#[test]
+fn test_scattering_pdfs(){
+    use palette::Srgb;
+    use material::Scatterable;
+    use vector::Vec3;
+
+    let ray = ray::Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(2.0, -2.0, 0.0));
+    let hit = hit_record::HitRecord::new(1.0, Vec3::default(), Vec3::new(0.0, 1.0, 0.0), true, material::Material::Lambertian(material::Lambertian::default()));
+    let up = Vec3::new(0.0, 3.0, 0.0);
+    let mirror = Vec3::new(1.0, 1.0, 0.0);
+
+    // Diffuse: cosine weighted, mirrors and glass have no density
+    let diffuse = material::Material::Lambertian(material::Lambertian::new(Srgb::new(0.5, 0.5, 0.5)));
+    assert!(!diffuse.is_specular());
+    assert_approx_eq!(diffuse.scattering_pdf(&ray, &hit, up), 1.0 / std::f64::consts::PI);
+    assert_approx_eq!(diffuse.scattering_pdf(&ray, &hit, -up), 0.0);
+    assert_approx_eq!(diffuse.evaluate(&ray, &hit, up).red as f64, 0.5 / std::f64::consts::PI, 1e-6);
+
+    let glass = material::Material::Glass(material::Glass::new(1.5));
+    assert!(glass.is_specular());
+    assert_approx_eq!(glass.scattering_pdf(&ray, &hit, mirror), 0.0);
+    assert!(material::Material::Metallic(material::Metal::new(Srgb::new(1.0, 1.0, 1.0), 0.0)).is_specular());
+
+    // Smooth pbr is only specular when fully metallic, otherwise its diffuse lobe keeps sampling light
+    let chrome = material::Material::Pbr(material::Pbr::new(Srgb::new(0.9, 0.9, 0.9), 1.0, 0.0));
+    assert!(chrome.is_specular());
+    let varnish = material::Material::Pbr(material::Pbr::new(Srgb::new(0.8, 0.2, 0.2), 0.0, 0.0));
+    assert!(!varnish.is_specular());
+    let fresnel = 0.04 + 0.96 * (1.0 - 0.5_f64.sqrt()).powi(5);
+    assert_approx_eq!(varnish.scattering_pdf(&ray, &hit, up), (1.0 - fresnel) / std::f64::consts::PI);
+    assert_approx_eq!(varnish.evaluate(&ray, &hit, up).red as f64, 0.8 * (1.0 - fresnel) / std::f64::consts::PI, 1e-6);
+    assert!(!material::Material::Pbr(material::Pbr::new(Srgb::new(0.9, 0.9, 0.9), 1.0, 0.2)).is_specular());
+
+    // Scatter tells which lobe it picked: mirror rays have no density, diffuse ones the density of the mix
+    let mut rng = sampler::IndependentSampler::new(5);
+    let (mut mirrored, mut diffused) = (0, 0);
+    for _ in 0..10_000 {
+        let (scattered, _, pdf) = varnish.scatter(&ray, &hit, &mut rng).unwrap();
+        if (scattered.direction().unit_vector() - mirror.unit_vector()).length() < 1e-9 {
+            assert!(pdf.is_none());
+            mirrored += 1;
+        } else {
+            assert_approx_eq!(pdf.unwrap(), varnish.scattering_pdf(&ray, &hit, scattered.direction()), 1e-12);
+            diffused += 1;
+        }
+    }
+    assert_approx_eq!(mirrored as f64 / 10_000.0, fresnel, 0.01);
+    assert!(diffused > 9_000);
+
+    // Fuzz as large as the reflection spreads it like a diffuse surface around the mirror direction
+    let rough = material::Material::Metallic(material::Metal::new(Srgb::new(0.8, 0.8, 0.8), 1.0));
+    assert!(!rough.is_specular());
+    assert_approx_eq!(rough.scattering_pdf(&ray, &hit, mirror), 1.0 / std::f64::consts::PI);
+    assert_approx_eq!(rough.scattering_pdf(&ray, &hit, up), 0.5_f64.sqrt() / std::f64::consts::PI);
+    assert_approx_eq!(rough.evaluate(&ray, &hit, Vec3::new(1.0, -0.1, 0.0)).red, 0.0);
+
+    // Densities add up to one over the sphere, and scattering matches evaluate over pdf
+    let materials = [
+        material::Material::Metallic(material::Metal::new(Srgb::new(0.8, 0.6, 0.4), 0.3)),
+        material::Material::Metallic(material::Metal::new(Srgb::new(0.8, 0.6, 0.4), 1.5)),
+        material::Material::Pbr(material::Pbr::new(Srgb::new(0.8, 0.6, 0.4), 0.5, 0.6)),
+    ];
+    let mut rng = sampler::IndependentSampler::new(3);
+    for material in materials {
+        let count = 200_000;
+        let mut total = 0.0;
+        for _ in 0..count {
+            total += material.scattering_pdf(&ray, &hit, Vec3::sample_unit_vec3(sampler::Sampler::get_2d(&mut rng))) * 4.0 * std::f64::consts::PI;
+        }
+        assert_approx_eq!(total / count as f64, 1.0, 0.05);
+
+        let mut checked = 0;
+        for _ in 0..100 {
+            if let Some((scattered, attenuation, pdf)) = material.scatter(&ray, &hit, &mut rng) {
+                let pdf = pdf.unwrap();
+                assert_approx_eq!(pdf, material.scattering_pdf(&ray, &hit, scattered.direction()), 1e-12);
+                let reflected = material.evaluate(&ray, &hit, scattered.direction());
+                // Pbr scatters by one lobe while evaluate covers both, so only metals match exactly
+                if let material::Material::Metallic(_) = material {
+                    assert_approx_eq!(reflected.green as f64 / pdf, attenuation.green as f64, 1e-4);
+                } else {
+                    assert!(reflected.green > 0.0 && pdf > 0.0);
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 50);
+    }
+}
+
+#[test]
+fn test_lights(){
+    use palette::Srgb;
+    use vector::Vec3;
+
+    let mut rng = sampler::IndependentSampler::new(4);
+    let origin = Vec3::default();
+
+    // Unit square one unit above: straight up it covers 1 / (cos * area) per unit solid angle
+    let square = light::Light::quad(Vec3::new(-0.5, 1.0, -0.5), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert_approx_eq!(square.pdf(origin, Vec3::new(0.0, 2.0, 0.0)), 1.0);
+    assert_approx_eq!(square.pdf(origin, Vec3::new(0.0, -1.0, 0.0)), 0.0);
+    assert_approx_eq!(square.pdf(origin, Vec3::new(2.0, 1.0, 0.0)), 0.0);
+    assert!(square.sample(Vec3::new(3.0, 1.0, 0.0), (0.5, 0.5)).is_none());
+
+    // Sphere covers a cone, from inside it can't be sampled
+    let ball = light::Light::sphere(Vec3::new(0.0, 0.0, -2.0), 1.0);
+    let cone = 2.0 * std::f64::consts::PI * (1.0 - 0.75_f64.sqrt());
+    assert_approx_eq!(ball.pdf(origin, Vec3::new(0.0, 0.0, -1.0)), 1.0 / cone);
+    assert_approx_eq!(ball.pdf(origin, Vec3::new(0.0, 1.0, -1.0)), 0.0);
+    assert!(ball.sample(Vec3::new(0.0, 0.0, -2.5), (0.5, 0.5)).is_none());
+
+    // Triangle is half of the square, off axis the distance grows and the cosine shrinks
+    // Disk seen straight on covers 1 / area, like the square
+    let half = light::Light::triangle(Vec3::new(-0.5, 1.0, -0.5), Vec3::new(0.5, 1.0, -0.5), Vec3::new(-0.5, 1.0, 0.5));
+    assert_approx_eq!(half.pdf(origin, Vec3::new(-0.1, 1.0, -0.1)), 2.0 * 1.02_f64.powf(1.5));
+    assert_approx_eq!(half.pdf(origin, Vec3::new(0.1, 1.0, 0.1)), 0.0);
+    let disk = light::Light::disk(Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 3.0, 0.0), 0.5);
+    assert_approx_eq!(disk.pdf(origin, Vec3::new(0.0, -1.0, 0.0)), 1.0 / (std::f64::consts::PI * 0.25));
+    assert_approx_eq!(disk.pdf(origin, Vec3::new(0.45, -1.0, 0.45)), 0.0);
+
+    for light in [square, ball, half, disk] {
+        // Sampled directions meet the light and densities add up to one over the sphere
+        for _ in 0..100 {
+            let (direction, pdf) = light.sample(origin, sampler::Sampler::get_2d(&mut rng)).unwrap();
+            assert_approx_eq!(light.pdf(origin, direction * 3.0), pdf, 1e-6 * pdf);
+        }
+
+        let count = 200_000;
+        let mut total = 0.0;
+        for _ in 0..count {
+            total += light.pdf(origin, Vec3::sample_unit_vec3(sampler::Sampler::get_2d(&mut rng))) * 4.0 * std::f64::consts::PI;
+        }
+        assert_approx_eq!(total / count as f64, 1.0, 0.05);
+    }
+
+    // Diffuse floor under a glowing ball in the dark: it's lit by pi * L * sin^2 of the cone angle, so the floor shows
+    // albedo * L * (r / d)^2. Registered as a light the ball is found at every hit and a few hundred paths are enough.
+    let glow = material::Material::DiffuseLight(material::DiffuseLight::new(Srgb::new(16.0, 16.0, 16.0)));
+    let gray = material::Material::Lambertian(material::Lambertian::new(Srgb::new(0.5, 0.5, 0.5)));
+    let mut world = hittable::HittableList::new();
+    world.add(plane::Plane::new(Vec3::default(), Vec3::new(0.0, 1.0, 0.0), gray));
+    world.add(sphere::Sphere::new(Vec3::new(0.0, 4.0, 0.0), 1.0, glow));
+    let scene = scene::Scene::new(world)
+        .with_environment(environment::Environment::Constant(Srgb::new(0.0, 0.0, 0.0)))
+        .with_light(light::Light::sphere(Vec3::new(0.0, 4.0, 0.0), 1.0));
+    assert_approx_eq!(scene.light_pdf(Vec3::new(0.0, 4.0, 0.0), Vec3::new(0.0, 1.0, 0.0)), 0.0);
+
+    let down = ray::Ray::new(Vec3::new(0.5, 1.0, 0.0), Vec3::new(-0.5, -1.0, 0.0));
+    let intensity = interval::Interval::new(0.001, f64::MAX);
+    let paths = 400;
+    let mut total = 0.0;
+    for _ in 0..paths {
+        total += ray::Ray::ray_color(&down, intensity, &scene, 2, &mut rng).red as f64;
+    }
+    assert_approx_eq!(total / paths as f64, 0.5, 0.015);
+
+    // Lights are found on the last bounce too
+    let mut total = 0.0;
+    for _ in 0..paths {
+        total += ray::Ray::ray_color(&down, intensity, &scene, 1, &mut rng).red as f64;
+    }
+    assert_approx_eq!(total / paths as f64, 0.5, 0.015);
+
+    // Lights of the scene file: glowing quads, spheres, disks and triangles are moved along with their transforms
+    let source = "[camera]\nwidth = 16\n\n[materials.lamp]\ntype = \"light\"\nemit = [4.0, 4.0, 4.0]\n\n[materials.ground]\ntype = \"lambertian\"\nalbedo = [0.5, 0.5, 0.5]\n\n\
+        [[objects]]\ntype = \"quad\"\norigin = [0.0, 0.0, 0.0]\nu = [1.0, 0.0, 0.0]\nv = [0.0, 0.0, 1.0]\nmaterial = \"lamp\"\ntransform = { translate = [0.0, 5.0, 0.0] }\n\n\
+        [[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, -3.0]\nradius = 0.5\nmaterial = \"lamp\"\n\n\
+        [[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 0.5\nmaterial = \"lamp\"\ntransform = { scale = 2.0, rotate = [0.0, 30.0, 0.0], translate = [3.0, 0.0, 0.0] }\n\n\
+        [[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 0.5\nmaterial = \"lamp\"\ntransform = { scale = [2.0, 1.0, 1.0] }\n\n\
+        [[objects]]\ntype = \"disk\"\ncenter = [0.0, 4.0, 0.0]\nnormal = [0.0, -1.0, 0.0]\nradius = 1.0\nmaterial = \"lamp\"\n\n\
+        [[objects]]\ntype = \"triangle\"\nvertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]\nmaterial = \"lamp\"\ntransform = { translate = [0.0, 0.0, -5.0] }\n\n\
+        [[objects]]\ntype = \"sphere\"\ncenter = [0.0, -100.0, 0.0]\nradius = 99.0\nmaterial = \"ground\"\n";
+    let description = scene_file::parse(source).unwrap();
+    // Stretched sphere isn't registered
+    assert_eq!(description.scene.lights.len(), 5);
+    match description.scene.lights[0] {
+        light::Light::Quad { origin, .. } => assert_approx_eq!(origin.y(), 5.0),
+        light => panic!("expected a quad light, got {:?}", light),
+    }
+    match description.scene.lights[2] {
+        light::Light::Sphere { center, radius } => {
+            assert_approx_eq!(center.x(), 3.0);
+            assert_approx_eq!(radius, 1.0);
+        }
+        light => panic!("expected a sphere light, got {:?}", light),
+    }
+    assert!(matches!(description.scene.lights[3], light::Light::Disk { .. }));
+    match description.scene.lights[4] {
+        light::Light::Triangle { origin, .. } => assert_approx_eq!(origin.z(), -5.0),
+        light => panic!("expected a triangle light, got {:?}", light),
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+use palette::{Srgb, LinSrgb};
+use crate::material::{Material, Pbr};
+
+// Image sampled with bilinear filtering, texture coordinates repeat outside of [0, 1].
+// Texel (0, 0) is the top left corner of the image, as in glTF.
+#[derive(Debug, Clone)]
+pub struct ImageTexture{
+    width: u32,
+    height: u32,
+    // Linear colors in row major order
+    pixels: Vec<Srgb>,
+}
+
+impl ImageTexture{
+    pub fn new(width: u32, height: u32, pixels: Vec<Srgb>) -> ImageTexture{
+        assert_eq!(pixels.len(), (width * height) as usize, "texture size doesn't match its pixels");
+
+        ImageTexture { width, height, pixels }
+    }
+
+    // Texture from 8-bit channels, `srgb` data (colors) is decoded to linear, other data (roughness, ...) is kept as is.
+    pub fn from_rgb8(width: u32, height: u32, rgb: &[[u8; 3]], srgb: bool) -> ImageTexture{
+        let pixels = rgb.iter().map(|texel| {
+            let color = Srgb::new(texel[0], texel[1], texel[2]).into_format::<f32>();
+
+            if srgb {
+                let linear: LinSrgb = color.into_linear();
+                Srgb::new(linear.red, linear.green, linear.blue)
+            } else {
+                color
+            }
+        }).collect();
+
+        ImageTexture::new(width, height, pixels)
+    }
+
+    pub fn width(&self) -> u32{
+        self.width
+    }
+
+    pub fn height(&self) -> u32{
+        self.height
+    }
+
+    // Texel at column x and row y, without filtering.
+    pub fn get(&self, x: u32, y: u32) -> Srgb{
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn sample(&self, u: f64, v: f64) -> Srgb{
+        // Texel centers sit at half integer positions.
+        let x = u * self.width as f64 - 0.5;
+        let y = v * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = ((x - x0) as f32, (y - y0) as f32);
+
+        let texel = |x: f64, y: f64| {
+            let x = (x as i64).rem_euclid(self.width as i64) as u32;
+            let y = (y as i64).rem_euclid(self.height as i64) as u32;
+
+            self.pixels[(y * self.width + x) as usize]
+        };
+
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1.0, y0) * fx;
+        let bottom = texel(x0, y0 + 1.0) * (1.0 - fx) + texel(x0 + 1.0, y0 + 1.0) * fx;
+
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+// Textures of a metallic-roughness material, their values multiply the factors of the material.
+#[derive(Debug, Clone, Default)]
+pub struct PbrTextures{
+    pub base_color: Option<Arc<ImageTexture>>,
+    // Roughness in the green channel and metalness in the blue one
+    pub metallic_roughness: Option<Arc<ImageTexture>>,
+    pub emissive: Option<Arc<ImageTexture>>,
+}
+
+impl PbrTextures{
+    pub fn is_empty(&self) -> bool{
+        self.base_color.is_none() && self.metallic_roughness.is_none() && self.emissive.is_none()
+    }
+
+    // Material with the textures looked up at (u, v), other materials are returned unchanged.
+    pub fn apply(&self, material: Material, u: f64, v: f64) -> Material{
+        let Material::Pbr(pbr) = material else {
+            return material
+        };
+
+        let mut base_color = pbr.base_color;
+        let mut metallic = pbr.metallic;
+        let mut roughness = pbr.roughness;
+        let mut emission = pbr.emission;
+
+        if let Some(texture) = &self.base_color {
+            let texel = texture.sample(u, v);
+            base_color = Srgb::new(base_color.red * texel.red, base_color.green * texel.green, base_color.blue * texel.blue);
+        }
+        if let Some(texture) = &self.metallic_roughness {
+            let texel = texture.sample(u, v);
+            roughness *= texel.green as f64;
+            metallic *= texel.blue as f64;
+        }
+        if let Some(texture) = &self.emissive {
+            let texel = texture.sample(u, v);
+            emission = Srgb::new(emission.red * texel.red, emission.green * texel.green, emission.blue * texel.blue);
+        }
+
+        Material::Pbr(Pbr::new(base_color, metallic, roughness).with_emission(emission))
+    }
+}
@@ -0,0 +1,53 @@
+use palette::{LinSrgb, Srgb};
+
+// Curve compressing high dynamic range linear values into displayable range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapper {
+    // Values above 1.0 are simply cut off
+    Clamp,
+    Reinhard,
+    // Narkowicz fit of the ACES filmic curve
+    AcesFilmic,
+}
+
+impl ToneMapper {
+    pub fn map(&self, x: f32) -> f32 {
+        match self {
+            ToneMapper::Clamp => x,
+            ToneMapper::Reinhard => x / (1.0 + x),
+            ToneMapper::AcesFilmic => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        }
+    }
+}
+
+// Turns linear framebuffer colors into sRGB encoded display colors: exposure, tone mapping and transfer function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputTransform {
+    // Exposure adjustment in stops, every stop doubles the brightness
+    pub exposure: f32,
+    pub tone_mapper: ToneMapper,
+}
+
+impl OutputTransform {
+    pub fn new(exposure: f32, tone_mapper: ToneMapper) -> OutputTransform {
+        OutputTransform { exposure, tone_mapper }
+    }
+
+    // Returns sRGB encoded color with every channel in 0.0..=1.0
+    pub fn apply(&self, linear: Srgb) -> Srgb {
+        let scale = self.exposure.exp2();
+        let map = |x: f32| {
+            let x = if x.is_nan() { 0.0 } else { x.max(0.0) * scale };
+
+            self.tone_mapper.map(x).clamp(0.0, 1.0)
+        };
+
+        Srgb::from_linear(LinSrgb::new(map(linear.red), map(linear.green), map(linear.blue)))
+    }
+}
+
+impl Default for OutputTransform {
+    fn default() -> Self {
+        OutputTransform { exposure: 0.0, tone_mapper: ToneMapper::Clamp }
+    }
+}
@@ -0,0 +1,148 @@
+use palette::Srgb;
+use crate::{ray, hit_record, vector::Vec3, aabb::Aabb, material::Material, hittable::Hittable};
+
+// Triangle with vertices in counter-clockwise order when looking at its front side.
+#[derive(Debug, Clone, Copy)]
+pub struct Triangle{
+    vertices: [Vec3; 3],
+    // Per-vertex shading normals, interpolated across the triangle for smooth shading
+    normals: Option<[Vec3; 3]>,
+    // Per-vertex texture coordinates
+    uvs: Option<[(f64, f64); 3]>,
+    // Per-vertex colors, interpolated and used as the material color
+    colors: Option<[Srgb; 3]>,
+    material: Material,
+}
+
+impl Triangle{
+    pub fn new(v0: Vec3, v1: Vec3, v2: Vec3, material: Material) -> Triangle {
+        Triangle {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: None,
+            colors: None,
+            material,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Triangle {
+        self.normals = Some(normals.map(|normal| normal.unit_vector()));
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Triangle {
+        self.uvs = Some(uvs);
+        self
+    }
+
+    pub fn with_colors(mut self, colors: [Srgb; 3]) -> Triangle {
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn vertices(&self) -> [Vec3; 3] {
+        self.vertices
+    }
+
+    // Barycentric weights of the ray intersection and its distance, computed in a ray aligned space so
+    // every point is tested by exactly the same edge functions by both triangles sharing an edge (Woop et al. 2013).
+    fn intersect(&self, ray: &ray::Ray, t_min: f64, t_max: f64) -> Option<(f64, [f64; 3])> {
+        let direction = to_array(ray.direction());
+
+        // Axis of the largest direction component becomes z, the other two follow in cyclic order.
+        let kz = (0..3).max_by(|a, b| direction[*a].abs().total_cmp(&direction[*b].abs())).unwrap();
+        let kx = (kz + 1) % 3;
+        let ky = (kx + 1) % 3;
+        let d = [direction[kx], direction[ky], direction[kz]];
+
+        // Shear so the ray goes down the +z axis from the origin.
+        let shear_x = -d[0] / d[2];
+        let shear_y = -d[1] / d[2];
+        let shear_z = 1.0 / d[2];
+
+        let p = self.vertices.map(|vertex| {
+            let p = to_array(vertex - ray.origin());
+            [p[kx] + shear_x * p[kz], p[ky] + shear_y * p[kz], p[kz]]
+        });
+
+        // Edge functions, all of the same sign when the ray passes through the triangle.
+        let e0 = p[1][0] * p[2][1] - p[1][1] * p[2][0];
+        let e1 = p[2][0] * p[0][1] - p[2][1] * p[0][0];
+        let e2 = p[0][0] * p[1][1] - p[0][1] * p[1][0];
+
+        if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+            return None
+        }
+
+        let determinant = e0 + e1 + e2;
+        if determinant == 0.0 {
+            return None
+        }
+
+        // Distance is kept scaled by the determinant, so the range test needs no division.
+        let t_scaled = (e0 * p[0][2] + e1 * p[1][2] + e2 * p[2][2]) * shear_z;
+        if determinant < 0.0 && (t_scaled >= t_min * determinant || t_scaled <= t_max * determinant) {
+            return None
+        }
+        if determinant > 0.0 && (t_scaled <= t_min * determinant || t_scaled >= t_max * determinant) {
+            return None
+        }
+
+        let inverse = 1.0 / determinant;
+
+        Some((t_scaled * inverse, [e0 * inverse, e1 * inverse, e2 * inverse]))
+    }
+}
+
+impl Hittable for Triangle{
+    fn hit(&self, ray: &ray::Ray, t_min: f64, t_max: f64) -> Option<hit_record::HitRecord> {
+        let (t, b) = self.intersect(ray, t_min, t_max)?;
+        let [v0, v1, v2] = self.vertices;
+
+        let uv = match self.uvs {
+            Some([uv0, uv1, uv2]) => (
+                b[0] * uv0.0 + b[1] * uv1.0 + b[2] * uv2.0,
+                b[0] * uv0.1 + b[1] * uv1.1 + b[2] * uv2.1,
+            ),
+            None => (b[1], b[2]),
+        };
+
+        // Geometric normal decides the front side, it is turned to agree with the shading normals when there are any.
+        let mut outward_normal = (v1 - v0).cross(&(v2 - v0)).unit_vector();
+        let shading_normal = self.normals
+            .map(|[n0, n1, n2]| n0 * b[0] + n1 * b[1] + n2 * b[2])
+            .filter(|normal| normal.length_squared() > 1e-12)
+            .map(|normal| normal.unit_vector());
+
+        if let Some(shading_normal) = shading_normal {
+            if shading_normal.dot(&outward_normal) < 0.0 {
+                outward_normal = -outward_normal;
+            }
+        }
+
+        let material = match self.colors {
+            Some([c0, c1, c2]) => self.material.with_albedo(c0 * b[0] as f32 + c1 * b[1] as f32 + c2 * b[2] as f32),
+            None => self.material,
+        };
+
+        let mut hit_record = hit_record::HitRecord::from_outward_normal(ray, t, outward_normal, uv, material);
+        if let Some(shading_normal) = shading_normal {
+            hit_record.normal = if hit_record.front_face { shading_normal } else { -shading_normal };
+        }
+
+        // Barycentric point is more accurate than stepping along the ray.
+        hit_record.point = v0 * b[0] + v1 * b[1] + v2 * b[2];
+
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [v0, v1, v2] = self.vertices;
+
+        Aabb::surrounding(&Aabb::from_points(v0, v1), &Aabb::from_points(v2, v2)).pad(0.0001)
+    }
+}
+
+fn to_array(v: Vec3) -> [f64; 3] {
+    [v.x(), v.y(), v.z()]
+}
@@ -1,5 +1,4 @@
 use std::ops::{Add, Mul, Sub, Div, Neg};
-use rand::Rng;
 
 
 #[derive(Debug, Clone, Copy)]
@@ -57,50 +56,43 @@ impl Vec3 {
     }
     
 
-    // fn random_vec3() -> Vec3 {
-    //     let mut random = rand::thread_rng();
+    // Maps a 2D sample from [0, 1)^2 to a uniformly distributed direction on the unit sphere.
+    pub fn sample_unit_vec3(u: (f64, f64)) -> Vec3 {
+        let z = 1.0 - 2.0 * u.0;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * u.1;
 
-    //     Vec3 { 
-    //         x: random.gen::<f64>(),
-    //         y: random.gen::<f64>(),
-    //         z: random.gen::<f64>() 
-    //     }
-    // }
-
-    fn random_vec3_minmax(min: f64, max: f64) -> Vec3 {
-        let mut random = rand::thread_rng();
-
-        Vec3 { 
-            x: random.gen_range(min..max),
-            y: random.gen_range(min..max),
-            z: random.gen_range(min..max),
-        }
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
     }
 
-    fn random_vec3_unit_sphere() -> Vec3 {
-        loop  {
-            let vector = Vec3::random_vec3_minmax(-1.0, 1.0);
+    // Maps a 2D sample from [0, 1)^2 to the unit disk, keeps stratification of samples (Shirley-Chiu concentric mapping).
+    pub fn sample_unit_disk(u: (f64, f64)) -> Vec3 {
+        let a = 2.0 * u.0 - 1.0;
+        let b = 2.0 * u.1 - 1.0;
 
-            if vector.length_squared() < 1.0 {
-                return vector
-            }
+        if a == 0.0 && b == 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0)
         }
-    }
 
-    pub fn random_unit_vec3() -> Vec3 {
-        Vec3::random_vec3_unit_sphere().unit_vector()
-    }
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, std::f64::consts::FRAC_PI_4 * (b / a))
+        } else {
+            (b, std::f64::consts::FRAC_PI_2 - std::f64::consts::FRAC_PI_4 * (a / b))
+        };
 
-    pub fn random_vec3_on_hemisphere(normal: &Vec3) -> Vec3 {
+        Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+    }
 
-        let unit_sphere_vec3 = Vec3::random_unit_vec3();
+    // Two unit vectors perpendicular to this unit vector and to each other (Duff et al. branchless basis).
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let sign = 1.0_f64.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
 
-        //Check if unit vector is in the same hempisphere as sphere normal
-        if unit_sphere_vec3.dot(normal) > 0.0 {
-            unit_sphere_vec3
-        }else {
-            unit_sphere_vec3 *  -1.0
-        }
+        (
+            Vec3::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Vec3::new(b, sign + self.y * self.y * a, -self.y),
+        )
     }
 
     pub fn near_zero(&self) -> bool {
@@ -232,3 +224,239 @@ impl Default for Vec3{
         Vec3 { x: 0.0, y: 0.0, z: 0.0 }
     }
 }
+
+// 4x4 matrix for affine transforms of points and vectors, stored by rows `m[row][column]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 { m: [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]] };
+
+    pub fn new(rows: [[f64; 4]; 4]) -> Mat4 {
+        Mat4 { m: rows }
+    }
+
+    // Matrix from columns, the order glTF and OpenGL keep matrices in.
+    pub fn from_columns(columns: [[f64; 4]; 4]) -> Mat4 {
+        Mat4::new(columns).transpose()
+    }
+
+    pub fn translate(offset: Vec3) -> Mat4 {
+        Mat4::new([[1.0, 0.0, 0.0, offset.x], [0.0, 1.0, 0.0, offset.y], [0.0, 0.0, 1.0, offset.z], [0.0, 0.0, 0.0, 1.0]])
+    }
+
+    pub fn scale(factors: Vec3) -> Mat4 {
+        Mat4::new([[factors.x, 0.0, 0.0, 0.0], [0.0, factors.y, 0.0, 0.0], [0.0, 0.0, factors.z, 0.0], [0.0, 0.0, 0.0, 1.0]])
+    }
+
+    // Counter-clockwise rotation by `degrees` around `axis` when looking against the axis (Rodrigues' formula).
+    pub fn rotate(axis: Vec3, degrees: f64) -> Mat4 {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        Mat4::new([
+            [t * a.x * a.x + cos, t * a.x * a.y - sin * a.z, t * a.x * a.z + sin * a.y, 0.0],
+            [t * a.x * a.y + sin * a.z, t * a.y * a.y + cos, t * a.y * a.z - sin * a.x, 0.0],
+            [t * a.x * a.z - sin * a.y, t * a.y * a.z + sin * a.x, t * a.z * a.z + cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Matrix product, applies `other` first and then this matrix.
+    pub fn compose(&self, other: &Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[row][k] * other.m[k][column]).sum();
+            }
+        }
+
+        Mat4::new(m)
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = self.m[column][row];
+            }
+        }
+
+        Mat4::new(m)
+    }
+
+    // Determinant of the upper 3x3 part, negative for mirroring transforms.
+    pub fn determinant3(&self) -> f64 {
+        let [r0, r1, r2] = self.rows3();
+
+        r0.dot(&r1.cross(&r2))
+    }
+
+    // Inverse by Gauss-Jordan elimination with partial pivoting, None for singular matrices.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inverse = Mat4::IDENTITY.m;
+
+        for column in 0..4 {
+            let pivot = (column..4).max_by(|i, j| a[*i][column].abs().total_cmp(&a[*j][column].abs()))?;
+            if a[pivot][column].abs() < 1e-12 {
+                return None
+            }
+
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for k in 0..4 {
+                a[column][k] *= scale;
+                inverse[column][k] *= scale;
+            }
+
+            for row in 0..4 {
+                let factor = a[row][column];
+                if row == column || factor == 0.0 {
+                    continue
+                }
+
+                for k in 0..4 {
+                    a[row][k] -= factor * a[column][k];
+                    inverse[row][k] -= factor * inverse[column][k];
+                }
+            }
+        }
+
+        Some(Mat4::new(inverse))
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        self.transform_vector(p) + Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    // Transforms a direction, translation doesn't apply to it.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let [r0, r1, r2] = self.rows3();
+
+        Vec3::new(r0.dot(&v), r1.dot(&v), r2.dot(&v))
+    }
+
+    fn rows3(&self) -> [Vec3; 3] {
+        [0, 1, 2].map(|row| Vec3::new(self.m[row][0], self.m[row][1], self.m[row][2]))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::IDENTITY
+    }
+}
+
+impl Mul<Mat4> for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        self.compose(&other)
+    }
+}
+
+// Invertible affine transform, keeps its inverse so rays can be taken into object space cheaply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    matrix: Mat4,
+    inverse: Mat4,
+    // Inverse transpose, computed once since every hit on a transformed object needs it.
+    normal: Mat4,
+}
+
+impl Transform {
+    // None when the matrix can't be inverted, e.g. scaled to zero along an axis.
+    pub fn new(matrix: Mat4) -> Option<Transform> {
+        Some(Transform::from_parts(matrix, matrix.inverse()?))
+    }
+
+    fn from_parts(matrix: Mat4, inverse: Mat4) -> Transform {
+        Transform { matrix, inverse, normal: inverse.transpose() }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::from_parts(Mat4::IDENTITY, Mat4::IDENTITY)
+    }
+
+    pub fn translate(offset: Vec3) -> Transform {
+        Transform::from_parts(Mat4::translate(offset), Mat4::translate(-offset))
+    }
+
+    pub fn rotate(axis: Vec3, degrees: f64) -> Transform {
+        let matrix = Mat4::rotate(axis, degrees);
+
+        // Rotations are orthogonal, their inverse is the transpose.
+        Transform::from_parts(matrix, matrix.transpose())
+    }
+
+    // None when any factor is zero.
+    pub fn scale(factors: Vec3) -> Option<Transform> {
+        if factors.x == 0.0 || factors.y == 0.0 || factors.z == 0.0 {
+            return None
+        }
+
+        Some(Transform::from_parts(Mat4::scale(factors), Mat4::scale(Vec3::new(1.0 / factors.x, 1.0 / factors.y, 1.0 / factors.z))))
+    }
+
+    // Transform doing this one first and `next` after it.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform::from_parts(next.matrix.compose(&self.matrix), self.inverse.compose(&next.inverse))
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform { matrix: self.inverse, inverse: self.matrix, normal: self.matrix.transpose() }
+    }
+
+    pub fn matrix(&self) -> &Mat4 {
+        &self.matrix
+    }
+
+    pub fn inverse_matrix(&self) -> &Mat4 {
+        &self.inverse
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    // Normals stay perpendicular to surfaces under the inverse transpose, result is a unit vector.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        self.normal.transform_vector(n).unit_vector()
+    }
+
+    // Factor lengths are multiplied by when the transform keeps shapes, None when it stretches or shears them.
+    pub fn uniform_scale(&self) -> Option<f64> {
+        let axes = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)].map(|axis| self.vector(axis));
+        let scale = axes[0].length();
+        let tolerance = 1e-9 * scale;
+
+        let same_lengths = axes.iter().all(|axis| (axis.length() - scale).abs() <= tolerance);
+        let perpendicular = axes[0].dot(&axes[1]).abs() <= tolerance * scale && axes[1].dot(&axes[2]).abs() <= tolerance * scale
+            && axes[2].dot(&axes[0]).abs() <= tolerance * scale;
+
+        (same_lengths && perpendicular).then_some(scale)
+    }
+
+    // Whether the transform mirrors, which turns counter-clockwise triangles clockwise.
+    pub fn is_mirroring(&self) -> bool {
+        self.matrix.determinant3() < 0.0
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}